@@ -0,0 +1,139 @@
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::process::Child;
+use tauri::{AppHandle, State, Emitter};
+use crate::utils::comparison::{self, Comparison, ComparisonAudio, ComparisonLayout};
+use crate::utils::ffmpeg;
+use crate::utils::probe;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ComparisonOptions {
+    pub a_path: String, // e.g. the input of denoise_video or convert_video
+    pub b_path: String, // e.g. its output
+    pub output_path: String, // MP4
+    pub layout: Option<String>, // "side_by_side" (default), "wipe" or "alternate"
+    pub labels: Option<bool>, // Draw the source names, default true
+    pub label_a: Option<String>, // Default "Before"
+    pub label_b: Option<String>, // Default "After"
+    pub split: Option<f64>, // Wipe divider position, 0.1-0.9 of the width, default 0.5
+    pub sweep: Option<bool>, // Move the wipe divider across and back instead, default false
+    pub interval: Option<f64>, // Seconds per source when alternating (video or audio) and per sweep, default 3
+    pub audio: Option<String>, // "a" (default), "b", "alternate" or "none"
+}
+
+/// Render two versions of a video into one for a before/after comparison.
+/// B is scaled to A's displayed size and frame rate; the render ends with the shorter source.
+#[tauri::command]
+pub async fn render_comparison(
+    app: AppHandle,
+    options: ComparisonOptions,
+    process_state: State<'_, Arc<Mutex<Option<Child>>>>,
+) -> Result<String, String> {
+    if !PathBuf::from(&options.a_path).exists() {
+        return Err("Source A does not exist".to_string());
+    }
+    if !PathBuf::from(&options.b_path).exists() {
+        return Err("Source B does not exist".to_string());
+    }
+
+    let layout = ComparisonLayout::parse(options.layout.as_deref())?;
+    let audio = ComparisonAudio::parse(options.audio.as_deref())?;
+    let split = options.split.unwrap_or(0.5);
+    if !(0.1..=0.9).contains(&split) {
+        return Err("Split position must be between 0.1 and 0.9".to_string());
+    }
+    let interval = options.interval.unwrap_or(comparison::DEFAULT_INTERVAL);
+    if !(0.5..=60.0).contains(&interval) {
+        return Err("Interval must be between 0.5 and 60 seconds".to_string());
+    }
+
+    let a = probe::probe_media(&app, &options.a_path).map_err(|e| e.message)?;
+    let b = probe::probe_media(&app, &options.b_path).map_err(|e| e.message)?;
+    let video = a
+        .video
+        .as_ref()
+        .ok_or_else(|| "Source A has no video track".to_string())?;
+    if b.video.is_none() {
+        return Err("Source B has no video track".to_string());
+    }
+    if audio == ComparisonAudio::Alternate && (a.audio.is_none() || b.audio.is_none()) {
+        return Err("Alternating audio needs an audio track in both sources".to_string());
+    }
+
+    // Frames are auto-rotated, so compose at the displayed size (even, for yuv420p)
+    let (width, height) = if video.rotation % 180 == 90 {
+        (video.height, video.width)
+    } else {
+        (video.width, video.height)
+    };
+    let (width, height) = (width - width % 2, height - height % 2);
+
+    let output_path = PathBuf::from(&options.output_path);
+    let output_dir = output_path
+        .parent()
+        .ok_or_else(|| "Invalid output path".to_string())?;
+    std::fs::create_dir_all(output_dir)
+        .map_err(|e| format!("Failed to create output directory: {}", e))?;
+
+    let label_a = options.label_a.as_deref().unwrap_or(comparison::DEFAULT_LABELS.0);
+    let label_b = options.label_b.as_deref().unwrap_or(comparison::DEFAULT_LABELS.1);
+    let job = Comparison {
+        layout,
+        width,
+        height,
+        fps: video.fps,
+        labels: options.labels.unwrap_or(true).then_some((label_a, label_b)),
+        split,
+        sweep: options.sweep.unwrap_or(false),
+        interval,
+        audio,
+    };
+
+    let mut args = vec![
+        "-i".to_string(),
+        options.a_path.clone(),
+        "-i".to_string(),
+        options.b_path.clone(),
+        "-filter_complex".to_string(),
+        comparison::comparison_graph(&job),
+        "-map".to_string(),
+        "[v]".to_string(),
+    ];
+    match audio {
+        ComparisonAudio::A => args.extend(vec!["-map".to_string(), "0:a:0?".to_string()]),
+        ComparisonAudio::B => args.extend(vec!["-map".to_string(), "1:a:0?".to_string()]),
+        ComparisonAudio::Alternate => args.extend(vec!["-map".to_string(), "[a]".to_string()]),
+        ComparisonAudio::None => args.push("-an".to_string()),
+    }
+    args.extend(ffmpeg::h264_aac_args());
+
+    // A mapped audio track would otherwise run on past the end of the shorter video
+    let shorter = a.duration.zip(b.duration).map(|(a, b)| a.min(b));
+    if let Some(shorter) = shorter {
+        args.push("-t".to_string());
+        args.push(format!("{:.3}", shorter));
+    }
+    args.push("-y".to_string());
+    args.push(options.output_path.clone());
+
+    // Clear any previous process
+    {
+        let mut state = process_state.lock().unwrap();
+        *state = None;
+    }
+
+    ffmpeg::execute_ffmpeg_pass(
+        &app,
+        args,
+        "conversion-progress",
+        process_state.inner().clone(),
+        shorter.or(a.duration).or(b.duration),
+        (0.0, 100.0),
+    )
+    .map_err(|e| format!("Failed to render comparison: {}", e.message))?;
+
+    app.emit("conversion-progress", 100.0).ok();
+
+    Ok(options.output_path)
+}
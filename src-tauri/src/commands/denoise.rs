@@ -5,20 +5,38 @@ use std::process::Child;
 use tauri::{AppHandle, State, Emitter};
 use crate::utils::ffmpeg;
 use crate::utils::deep_filter;
+use crate::utils::audio_filters::{self, AudioOptions};
+use crate::utils::filter_graph::FilterGraph;
+use crate::utils::loudness::{self, LoudnessOptions, LoudnessReport};
+use crate::utils::probe;
+use crate::utils::rotation::{self, RotationOptions};
+use crate::utils::video_denoise;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct DenoiseOptions {
     pub input_path: String,
     pub output_path: String,
+    pub rotation: Option<RotationOptions>, // "metadata" mode copies the video stream instead of re-encoding
+    pub audio: Option<bool>, // Clean the audio with deep-filter (default true)
+    pub video_denoise: Option<String>, // "hqdn3d" (fast) or "nlmeans" (quality), picture is untouched when omitted
+    pub video_strength: Option<String>, // "light", "medium" (default) or "strong"
+    pub loudness: Option<LoudnessOptions>, // Two-pass EBU R128 loudness normalization of the cleaned audio
+    pub audio_options: Option<AudioOptions>, // Gain, fades, channel mixdown, sample rate and bitrate
 }
 
-/// Denoise video audio using deep-filter
+#[derive(Debug, Serialize)]
+pub struct DenoiseResult {
+    pub output_path: String,
+    pub loudness: Option<LoudnessReport>, // Before/after measurements when normalizing
+}
+
+/// Denoise video audio using deep-filter and/or the picture using FFmpeg filters
 #[tauri::command]
 pub async fn denoise_video(
     app: AppHandle,
     options: DenoiseOptions,
     process_state: State<'_, Arc<Mutex<Option<Child>>>>,
-) -> Result<String, String> {
+) -> Result<DenoiseResult, String> {
     let input_path = PathBuf::from(&options.input_path);
     if !input_path.exists() {
         return Err("Input file does not exist".to_string());
@@ -33,6 +51,126 @@ pub async fn denoise_video(
     std::fs::create_dir_all(output_dir)
         .map_err(|e| format!("Failed to create output directory: {}", e))?;
 
+    let media = probe::probe_media(&app, &options.input_path).map_err(|e| e.message)?;
+    let source_rotation = media.video.as_ref().map(|v| v.rotation).unwrap_or(0);
+
+    // Prepare the video side of the final combine step up front so invalid options fail early
+    let metadata_rotation = options.rotation.as_ref().filter(|r| r.is_metadata_mode());
+    let mut video_input_args = Vec::new();
+    let mut video_filter_args = Vec::new();
+    if let Some(rotation_options) = metadata_rotation {
+        if options.video_denoise.is_some() {
+            return Err("Metadata rotation copies the video stream and cannot be combined with video denoising".to_string());
+        }
+        // Only the display matrix changes, the video stream is copied as-is
+        video_input_args.extend(rotation::metadata_input_args(&app, source_rotation, rotation_options)?);
+        video_input_args.extend(vec!["-i".to_string(), options.input_path.clone()]);
+    } else {
+        let mut graph = FilterGraph::new();
+        rotation::apply_transpose(&mut graph, options.rotation.as_ref())?;
+        if let Some(mode) = options.video_denoise.as_deref() {
+            video_denoise::apply_video_denoise(&mut graph, mode, options.video_strength.as_deref())?;
+        }
+        graph.video("scale=iw:ih"); // Keep original resolution but ensure compatibility
+        video_input_args.extend(graph.input_args(&options.input_path));
+        video_filter_args.extend(graph.output_args());
+    }
+
+    let loudness_target = match options.loudness.as_ref() {
+        Some(loudness_options) => {
+            if media.audio.is_none() {
+                return Err("Input has no audio to normalize".to_string());
+            }
+            Some(loudness_options.target()?)
+        }
+        None => None,
+    };
+
+    // Mixdown and gain go before the loudness measurement and normalization, fades after them
+    let (level_filters, fade_filters, audio_output_args) = match options.audio_options.as_ref() {
+        Some(audio_options) => (
+            match media.audio.as_ref() {
+                Some(source) => audio_filters::level_filters(audio_options, source)?,
+                None => Vec::new(),
+            },
+            audio_filters::fade_filters(audio_options, media.duration)?,
+            audio_filters::audio_output_args(audio_options)?,
+        ),
+        None => (Vec::new(), Vec::new(), Vec::new()),
+    };
+
+    // Picture-only denoising runs as a single FFmpeg pass without deep-filter
+    if !options.audio.unwrap_or(true) {
+        if options.video_denoise.is_none() && loudness_target.is_none() && options.audio_options.is_none() {
+            return Err("Nothing to denoise: enable audio or video denoising".to_string());
+        }
+
+        // Clear any previous process
+        {
+            let mut state = process_state.lock().unwrap();
+            *state = None;
+        }
+
+        // Measure the original audio when normalizing loudness
+        let mut audio_filters = Vec::new();
+        if media.audio.is_some() {
+            audio_filters.extend(level_filters.iter().cloned());
+        }
+        let mut first_pass = None;
+        if let Some(target) = loudness_target.as_ref() {
+            let pass = loudness::measure_loudness(
+                &app,
+                &options.input_path,
+                &level_filters,
+                target,
+                process_state.inner().clone(),
+                media.duration,
+                (0.0, 30.0),
+            )
+            .map_err(|e| format!("Failed to measure loudness: {}", e.message))?;
+            let sample_rate = media.audio.as_ref().and_then(|a| a.sample_rate).unwrap_or(48000);
+            audio_filters.extend(loudness::normalization_filters(target, &pass, sample_rate));
+            first_pass = Some(pass);
+        }
+        if media.audio.is_some() {
+            audio_filters.extend(fade_filters);
+        }
+
+        let mut args = vec!["-y".to_string()];
+        args.extend(video_input_args);
+        args.extend(vec![
+            "-map".to_string(),
+            "0:v".to_string(),
+            "-map".to_string(),
+            "0:a?".to_string(), // Keep the original audio if there is any
+        ]);
+        args.extend(video_filter_args);
+        if !audio_filters.is_empty() {
+            args.push("-af".to_string());
+            args.push(audio_filters.join(","));
+        }
+        args.extend(video_codec_args(metadata_rotation.is_some()));
+        args.extend(audio_codec_args());
+        args.extend(audio_output_args);
+        args.push(options.output_path.clone());
+
+        let progress_range = if first_pass.is_some() { (30.0, 100.0) } else { (0.0, 100.0) };
+        let log = ffmpeg::execute_ffmpeg_capture(
+            &app,
+            args,
+            "conversion-progress",
+            process_state.inner().clone(),
+            None,
+            progress_range,
+        )
+        .map_err(|e| format!("Failed to denoise video: {}", e.message))?;
+
+        return Ok(DenoiseResult {
+            output_path: options.output_path,
+            loudness: loudness_report(loudness_target, first_pass, &log),
+        });
+    }
+
     // Get base name without extension
     let base_name = input_path
         .file_stem()
@@ -132,46 +270,61 @@ pub async fn denoise_video(
         return Err("Denoised WAV file appears to be corrupted (size < 1000 bytes)".to_string());
     }
 
+    // Measure the cleaned audio when normalizing loudness
+    let mut audio_filters = level_filters.clone();
+    let mut first_pass = None;
+    if let Some(target) = loudness_target.as_ref() {
+        // Clear process state for the measurement pass
+        {
+            let mut state = process_state.lock().unwrap();
+            *state = None;
+        }
+
+        let pass = loudness::measure_loudness(
+            &app,
+            &denoised_wav.to_string_lossy(),
+            &level_filters,
+            target,
+            process_state.inner().clone(),
+            media.duration,
+            (66.0, 70.0),
+        )
+        .map_err(|e| {
+            // Cleanup on error
+            let _ = std::fs::remove_dir_all(&temp_dir);
+            format!("Failed to measure loudness: {}", e.message)
+        })?;
+        audio_filters.extend(loudness::normalization_filters(target, &pass, 48000));
+        first_pass = Some(pass);
+    }
+    audio_filters.extend(fade_filters);
+
     // Step 3: Combine original video with denoised audio (66-100%)
     app.emit("conversion-progress", 66.0).ok();
 
-    let combine_args = vec![
-        "-y".to_string(),
-        "-i".to_string(),
-        options.input_path.clone(),
+    let mut combine_args = vec!["-y".to_string()];
+    combine_args.extend(video_input_args);
+    combine_args.extend(vec![
         "-i".to_string(),
         denoised_wav.to_string_lossy().to_string(),
         "-map".to_string(),
         "0:v".to_string(), // Map video from first input
         "-map".to_string(),
         "1:a".to_string(), // Map audio from second input
-        "-c:v".to_string(),
-        "libx264".to_string(), // Re-encode video for browser compatibility
-        "-c:a".to_string(),
-        "aac".to_string(), // Encode audio as AAC
-        "-preset".to_string(),
-        "medium".to_string(),
-        "-crf".to_string(),
-        "23".to_string(), // Quality setting
-        "-movflags".to_string(),
-        "+faststart".to_string(), // Enable fast start for web playback
-        "-pix_fmt".to_string(),
-        "yuv420p".to_string(), // Ensure browser-compatible pixel format
-        "-profile:v".to_string(),
-        "high".to_string(), // H.264 profile for better compatibility
-        "-level".to_string(),
-        "4.0".to_string(), // H.264 level
-        "-b:a".to_string(),
-        "192k".to_string(), // Audio bitrate
-        "-strict".to_string(),
-        "-2".to_string(), // Allow experimental codecs (for AAC)
+    ]);
+    combine_args.extend(video_filter_args);
+    if !audio_filters.is_empty() {
+        combine_args.push("-af".to_string());
+        combine_args.push(audio_filters.join(","));
+    }
+
+    combine_args.extend(video_codec_args(metadata_rotation.is_some()));
+    combine_args.extend(audio_codec_args());
+    combine_args.extend(audio_output_args);
+    combine_args.extend(vec![
         "-shortest".to_string(), // Finish encoding when the shortest input stream ends
-        "-vf".to_string(),
-        "scale=iw:ih".to_string(), // Keep original resolution but ensure compatibility
-        "-max_muxing_queue_size".to_string(),
-        "1024".to_string(), // Increase muxing queue size for large files
         options.output_path.clone(),
-    ];
+    ]);
 
     // Clear process state for final ffmpeg
     {
@@ -181,11 +334,13 @@ pub async fn denoise_video(
 
     // Execute video combination
     let combine_progress_state = process_state.inner().clone();
-    ffmpeg::execute_ffmpeg_with_progress(
+    let combine_log = ffmpeg::execute_ffmpeg_capture(
         &app,
         combine_args,
         "conversion-progress",
         combine_progress_state.clone(),
+        None,
+        (0.0, 100.0),
     )
     .map_err(|e| {
         // Cleanup on error
@@ -218,6 +373,65 @@ pub async fn denoise_video(
     // Emit final progress
     app.emit("conversion-progress", 100.0).ok();
 
-    Ok(options.output_path)
+    Ok(DenoiseResult {
+        output_path: options.output_path,
+        loudness: loudness_report(loudness_target, first_pass, &combine_log),
+    })
+}
+
+/// Combine the first pass measurement with the result printed by the normalizing pass
+fn loudness_report(
+    target: Option<loudness::LoudnessTarget>,
+    first_pass: Option<loudness::FirstPass>,
+    log: &str,
+) -> Option<LoudnessReport> {
+    match (target, first_pass) {
+        (Some(target), Some(first_pass)) => Some(LoudnessReport {
+            target,
+            before: first_pass.measurement,
+            after: loudness::parse_normalized_loudness(log),
+        }),
+        _ => None,
+    }
+}
+
+/// Video encoding settings for the denoised output
+fn video_codec_args(copy_video: bool) -> Vec<String> {
+    if copy_video {
+        return vec![
+            "-c:v".to_string(),
+            "copy".to_string(),
+        ];
+    }
+
+    vec![
+        "-c:v".to_string(),
+        "libx264".to_string(), // Re-encode video for browser compatibility
+        "-preset".to_string(),
+        "medium".to_string(),
+        "-crf".to_string(),
+        "23".to_string(), // Quality setting
+        "-pix_fmt".to_string(),
+        "yuv420p".to_string(), // Ensure browser-compatible pixel format
+        "-profile:v".to_string(),
+        "high".to_string(), // H.264 profile for better compatibility
+        "-level".to_string(),
+        "4.0".to_string(), // H.264 level
+    ]
 }
 
+/// Audio encoding and muxing settings for the denoised output
+fn audio_codec_args() -> Vec<String> {
+    vec![
+        "-c:a".to_string(),
+        "aac".to_string(), // Encode audio as AAC
+        "-b:a".to_string(),
+        "192k".to_string(), // Audio bitrate
+        "-strict".to_string(),
+        "-2".to_string(), // Allow experimental codecs (for AAC)
+        "-movflags".to_string(),
+        "+faststart".to_string(), // Enable fast start for web playback
+        "-max_muxing_queue_size".to_string(),
+        "1024".to_string(), // Increase muxing queue size for large files
+    ]
+}
@@ -2,4 +2,12 @@ pub mod preview;
 pub mod settings;
 pub mod video;
 pub mod denoise;
-
+pub mod stabilize;
+pub mod silence;
+pub mod scene;
+pub mod sprites;
+pub mod waveform;
+pub mod playback;
+pub mod stream;
+pub mod quality;
+pub mod comparison;
@@ -0,0 +1,214 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::Child;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, State};
+use crate::utils::ffmpeg;
+use crate::utils::media_protocol::MediaAccess;
+use crate::utils::probe;
+
+const PLAYLIST_NAME: &str = "index.m3u8";
+const INIT_SEGMENT_NAME: &str = "init.mp4";
+const SEGMENT_SECONDS: u32 = 2;
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The live transcode behind the player, separate from the conversion process so
+/// previewing a file does not cancel (or get cancelled by) a running job
+#[derive(Default)]
+pub struct PlaybackSessions {
+    current: Mutex<Option<PlaybackSession>>,
+}
+
+struct PlaybackSession {
+    child: Child,
+    dir: PathBuf,
+}
+
+impl PlaybackSessions {
+    /// Kill the running transcode, if any, and delete its segments
+    pub fn stop(&self, media_access: &MediaAccess) {
+        let session = self.current.lock().unwrap().take();
+        if let Some(mut session) = session {
+            session.child.kill().ok();
+            session.child.wait().ok();
+            media_access.revoke_dir(&session.dir);
+            std::fs::remove_dir_all(&session.dir).ok();
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PlaybackOptions {
+    pub input_path: String,
+    pub start: Option<f64>, // Seconds into the source to start from; seeking starts a new session
+}
+
+#[derive(Debug, Serialize)]
+pub struct PlaybackInfo {
+    pub playlist_path: String, // HLS playlist of fMP4 segments, served through the media protocol
+    pub start: f64, // Source time of the first segment
+    pub duration: Option<f64>, // Duration of the whole source
+    pub mime_type: String, // For MediaSource.addSourceBuffer
+}
+
+/// Start transcoding a source the webview cannot play natively (MKV, AVI, WMV,
+/// FLV, HEVC, ...) into short H.264/AAC fMP4 segments, replacing any previous session
+#[tauri::command]
+pub async fn start_playback(
+    app: AppHandle,
+    options: PlaybackOptions,
+    sessions: State<'_, PlaybackSessions>,
+    media_access: State<'_, MediaAccess>,
+) -> Result<PlaybackInfo, String> {
+    let input_path = PathBuf::from(&options.input_path);
+    if !input_path.exists() {
+        return Err("Input file does not exist".to_string());
+    }
+
+    sessions.stop(&media_access);
+
+    let media = probe::probe_media(&app, &options.input_path).map_err(|e| e.message)?;
+    if media.video.is_none() {
+        return Err("Input has no video track".to_string());
+    }
+    let start = match (options.start.unwrap_or(0.0).max(0.0), media.duration) {
+        (start, Some(duration)) if start >= duration => (duration - 1.0).max(0.0),
+        (start, _) => start,
+    };
+
+    let dir = ffmpeg::job_temp_dir("playback");
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create playback folder: {}", e))?;
+
+    let child = ffmpeg::spawn_ffmpeg(&app, transcode_args(&options.input_path, start, &dir))
+        .map_err(|e| e.message)?;
+    media_access.allow_dir(&dir);
+    *sessions.current.lock().unwrap() = Some(PlaybackSession {
+        child,
+        dir: dir.clone(),
+    });
+
+    // Hand over once the first segment exists so the player never sees an empty playlist
+    let playlist_path = dir.join(PLAYLIST_NAME);
+    let deadline = Instant::now() + STARTUP_TIMEOUT;
+    loop {
+        if has_first_segment(&playlist_path) && dir.join(INIT_SEGMENT_NAME).exists() {
+            break;
+        }
+
+        let exited = {
+            let mut current = sessions.current.lock().unwrap();
+            match current.as_mut() {
+                Some(session) if session.dir == dir => session.child.try_wait().ok().flatten().is_some(),
+                // Replaced or stopped while starting up
+                _ => return Err("Playback was stopped".to_string()),
+            }
+        };
+        // A very short source may finish before the first poll
+        if exited && !has_first_segment(&playlist_path) {
+            sessions.stop(&media_access);
+            return Err("FFmpeg could not transcode this file for playback".to_string());
+        }
+        if Instant::now() > deadline {
+            sessions.stop(&media_access);
+            return Err("Timed out waiting for playback to start".to_string());
+        }
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+
+    let mime_type = if media.audio.is_some() {
+        "video/mp4; codecs=\"avc1.640029,mp4a.40.2\""
+    } else {
+        "video/mp4; codecs=\"avc1.640029\""
+    };
+
+    Ok(PlaybackInfo {
+        playlist_path: playlist_path.to_string_lossy().to_string(),
+        start,
+        duration: media.duration,
+        mime_type: mime_type.to_string(),
+    })
+}
+
+/// Stop the live transcode, e.g. when the player closes or switches to a native file
+#[tauri::command]
+pub async fn stop_playback(
+    sessions: State<'_, PlaybackSessions>,
+    media_access: State<'_, MediaAccess>,
+) -> Result<(), String> {
+    sessions.stop(&media_access);
+    Ok(())
+}
+
+/// Low-latency H.264/AAC transcode into an event playlist of fMP4 segments.
+/// Fixed keyframe intervals keep every segment independently decodable.
+fn transcode_args(input_path: &str, start: f64, dir: &Path) -> Vec<String> {
+    let mut args = vec!["-v".to_string(), "error".to_string()];
+    if start > 0.0 {
+        args.push("-ss".to_string());
+        args.push(format!("{:.3}", start));
+    }
+    args.extend(vec![
+        "-i".to_string(),
+        input_path.to_string(),
+        "-map".to_string(),
+        "0:v:0".to_string(),
+        "-map".to_string(),
+        "0:a:0?".to_string(),
+        "-sn".to_string(),
+        "-dn".to_string(),
+        "-vf".to_string(),
+        "scale=trunc(iw/2)*2:trunc(ih/2)*2".to_string(),
+        "-c:v".to_string(),
+        "libx264".to_string(),
+        "-preset".to_string(),
+        "veryfast".to_string(),
+        "-tune".to_string(),
+        "zerolatency".to_string(),
+        "-profile:v".to_string(),
+        "high".to_string(),
+        "-level".to_string(),
+        "4.1".to_string(),
+        "-crf".to_string(),
+        "23".to_string(),
+        "-pix_fmt".to_string(),
+        "yuv420p".to_string(),
+        "-force_key_frames".to_string(),
+        format!("expr:gte(t,n_forced*{})", SEGMENT_SECONDS),
+        "-sc_threshold".to_string(),
+        "0".to_string(),
+        "-c:a".to_string(),
+        "aac".to_string(),
+        "-ac".to_string(),
+        "2".to_string(),
+        "-b:a".to_string(),
+        "128k".to_string(),
+        "-f".to_string(),
+        "hls".to_string(),
+        "-hls_time".to_string(),
+        SEGMENT_SECONDS.to_string(),
+        "-hls_list_size".to_string(),
+        "0".to_string(),
+        "-hls_playlist_type".to_string(),
+        "event".to_string(),
+        "-hls_segment_type".to_string(),
+        "fmp4".to_string(),
+        "-hls_fmp4_init_filename".to_string(),
+        INIT_SEGMENT_NAME.to_string(),
+        "-hls_flags".to_string(),
+        "independent_segments+temp_file".to_string(),
+        "-hls_segment_filename".to_string(),
+        dir.join("seg_%05d.m4s").to_string_lossy().to_string(),
+        "-y".to_string(),
+        dir.join(PLAYLIST_NAME).to_string_lossy().to_string(),
+    ]);
+    args
+}
+
+fn has_first_segment(playlist_path: &Path) -> bool {
+    std::fs::read_to_string(playlist_path)
+        .map(|playlist| playlist.contains("#EXTINF"))
+        .unwrap_or(false)
+}
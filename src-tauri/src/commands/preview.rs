@@ -1,25 +1,68 @@
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 use std::process::Child;
 use tauri::{AppHandle, State};
+use crate::commands::settings;
+use crate::utils::animation;
+use crate::utils::audio_image::{self, AudioImageStyle};
+use crate::utils::cache::{self, SourceStamp};
+use crate::utils::contact_sheet::{self, SheetLayout};
 use crate::utils::ffmpeg;
+use crate::utils::filter_graph::FilterGraph;
+use crate::utils::highlights;
+use crate::utils::media_protocol::{self, MediaAccess, MediaFile};
+use crate::utils::probe::{self, MediaInfo};
+use crate::utils::rotation::{self, RotationOptions};
+use crate::utils::scene;
+use crate::utils::watermark::{self, WatermarkOptions};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct PreviewOptions {
     pub input_path: String,
     pub output_path: String,
-    pub preview_type: String, // "thumbnail" or "clip"
-    pub timestamp: Option<f64>, // For thumbnail, time in seconds
+    pub preview_type: String, // "thumbnail", "best_frame", "clip", "highlights", "contact_sheet", "gif", "webp", "waveform" or "spectrogram"
+    pub timestamp: Option<f64>, // Time in seconds of the thumbnail, or where best_frame searches and clip/gif/webp start
+    pub preset: Option<String>, // Name of a preset from settings (clip, highlights, gif and webp)
+    pub watermark: Option<WatermarkOptions>, // Clip, highlights, gif and webp, overrides the preset's watermark
+    pub rotation: Option<RotationOptions>, // Previews are re-encoded, so this always transposes
+    pub columns: Option<u32>, // Contact sheet grid, default 4x4
+    pub rows: Option<u32>,
+    pub tile_width: Option<u32>, // Contact sheet tile width in pixels, default 320
+    pub sheet_mode: Option<String>, // "even" (default) or "scenes"
+    pub show_timestamps: Option<bool>, // Label contact sheet tiles, default true
+    pub show_header: Option<bool>, // File details above the contact sheet, default true
+    pub image_format: Option<String>, // Contact sheet "jpeg", "png" or "webp", default from the output extension
+    pub duration: Option<f64>, // Clip length in seconds (default 5), or gif/webp length (default 3)
+    pub fps: Option<f64>, // Gif/webp frame rate, default 12
+    pub width: Option<u32>, // Gif/webp width in pixels (default 480), or waveform/spectrogram width (default 1280)
+    pub loop_count: Option<u32>, // Gif/webp number of plays, 0 loops forever (default)
+    pub dither: Option<String>, // Gif "sierra2_4a" (default), "floyd_steinberg", "bayer" or "none"
+    pub segment_count: Option<u32>, // Highlights segments, default 5
+    pub segment_length: Option<f64>, // Highlights seconds per segment, default 2
+    pub crossfade: Option<f64>, // Highlights crossfade in seconds, default 0.5
+    pub highlight_mode: Option<String>, // "even" (default) or "scenes"
+    pub height: Option<u32>, // Waveform (default 240) or spectrogram (default 512) height, per input
+    pub color: Option<String>, // Waveform colour, e.g. "0x3b82f6", or spectrogram scheme, e.g. "intensity"
+    pub split_channels: Option<bool>, // Draw each audio channel separately
+    pub scale: Option<String>, // Waveform amplitude or spectrogram frequency scale, "linear" (default) or "log"
+    pub compare_path: Option<String>, // Waveform/spectrogram of this file (e.g. the denoised one) is drawn below the input's
 }
 
-/// Generate preview (thumbnail or clip)
+#[derive(Debug, Serialize)]
+pub struct PreviewResult {
+    pub output_path: String,
+    pub file_size: u64, // Bytes
+}
+
+/// Generate preview (thumbnail, best frame, clip, highlights, contact sheet, animated gif/webp,
+/// or a waveform/spectrogram image of the audio)
 #[tauri::command]
 pub async fn generate_preview(
     app: AppHandle,
     options: PreviewOptions,
     process_state: State<'_, Arc<Mutex<Option<Child>>>>,
-) -> Result<String, String> {
+) -> Result<PreviewResult, String> {
     let input_path = PathBuf::from(&options.input_path);
     if !input_path.exists() {
         return Err("Input file does not exist".to_string());
@@ -41,27 +84,199 @@ pub async fn generate_preview(
         timestamp % 60.0
     );
 
-    let mut args = vec!["-i".to_string(), options.input_path.clone()];
+    let media = probe::probe_media(&app, &options.input_path).map_err(|e| e.message)?;
+    let preview_type = options.preview_type.to_lowercase();
+    let mut output_file = options.output_path.clone();
+    let mut progress_range = (0.0, 100.0);
+    let mut expected_duration = None;
+    let columns = options.columns.unwrap_or(contact_sheet::DEFAULT_COLUMNS);
+    let rows = options.rows.unwrap_or(contact_sheet::DEFAULT_ROWS);
+
+    // Clear any previous process
+    {
+        let mut state = process_state.lock().unwrap();
+        *state = None;
+    }
+
+    if preview_type == "waveform" || preview_type == "spectrogram" {
+        return generate_audio_image(&app, &options, &preview_type, &media, process_state.inner().clone());
+    }
+
+    let mut graph = FilterGraph::new();
+    if preview_type == "best_frame" {
+        // Pick the most representative frame of the next batch instead of a fixed one
+        graph.video(format!("thumbnail={}", scene::THUMBNAIL_BATCH));
+    }
+    if preview_type == "contact_sheet" {
+        let duration = media
+            .duration
+            .ok_or_else(|| "Could not determine the input duration".to_string())?;
+
+        let times = match options.sheet_mode.as_deref().unwrap_or("even").to_lowercase().as_str() {
+            "even" => contact_sheet::even_timestamps(columns * rows, duration),
+            "scenes" => {
+                let scenes = scene::detect_scenes(
+                    &app,
+                    &options.input_path,
+                    0.3,
+                    process_state.inner().clone(),
+                    Some(duration),
+                    (0.0, 50.0),
+                )
+                .map_err(|e| e.message)?;
+                progress_range = (50.0, 100.0);
+
+                // Fall back to even spacing for footage without cuts
+                let starts = scene::scene_starts(&scenes, 1.0);
+                if starts.len() < 2 {
+                    contact_sheet::even_timestamps(columns * rows, duration)
+                } else {
+                    contact_sheet::scene_timestamps(&starts, duration, columns * rows)
+                }
+            }
+            other => {
+                return Err(format!("Invalid contact sheet mode: {}. Use 'even' or 'scenes'", other));
+            }
+        };
+        graph.video(contact_sheet::frame_select_filter(&times));
+    }
+    if preview_type == "highlights" {
+        let duration = media
+            .duration
+            .ok_or_else(|| "Could not determine the input duration".to_string())?;
+        let count = options.segment_count.unwrap_or(highlights::DEFAULT_SEGMENTS);
+        let length = options.segment_length.unwrap_or(highlights::DEFAULT_SEGMENT_LENGTH);
+        if duration < length * 2.0 {
+            return Err("The file is too short for a highlights preview".to_string());
+        }
+
+        let starts = match options.highlight_mode.as_deref().unwrap_or("even").to_lowercase().as_str() {
+            "even" => highlights::even_segment_starts(count, length, duration),
+            "scenes" => {
+                let scenes = scene::detect_scenes(
+                    &app,
+                    &options.input_path,
+                    0.3,
+                    process_state.inner().clone(),
+                    Some(duration),
+                    (0.0, 50.0),
+                )
+                .map_err(|e| e.message)?;
+                progress_range = (50.0, 100.0);
 
-    match options.preview_type.to_lowercase().as_str() {
+                // Skip the opening scene at 0 and fall back to even spacing for footage without cuts
+                let starts = scene::scene_starts(&scenes, length);
+                if starts.len() <= 2 {
+                    highlights::even_segment_starts(count, length, duration)
+                } else {
+                    highlights::scene_segment_starts(&starts[1..], count, length, duration)
+                }
+            }
+            other => {
+                return Err(format!("Invalid highlights mode: {}. Use 'even' or 'scenes'", other));
+            }
+        };
+
+        let plan = highlights::HighlightPlan::new(
+            starts,
+            length,
+            options.crossfade.unwrap_or(highlights::DEFAULT_CROSSFADE),
+        )?;
+        let fps = media.video.as_ref().and_then(|v| v.fps).unwrap_or(30.0);
+        highlights::apply_highlights(
+            &mut graph,
+            &options.input_path,
+            &plan,
+            fps,
+            media.audio.is_some(),
+        );
+        expected_duration = Some(plan.total_duration());
+    }
+    rotation::apply_transpose(&mut graph, options.rotation.as_ref())?;
+    if preview_type == "contact_sheet" {
+        let header = if options.show_header.unwrap_or(true) {
+            Some(contact_sheet::header_text(&options.input_path, &media))
+        } else {
+            None
+        };
+        contact_sheet::apply_sheet_layout(&mut graph, &SheetLayout {
+            columns,
+            rows,
+            tile_width: options.tile_width.unwrap_or(contact_sheet::DEFAULT_TILE_WIDTH),
+            timestamps: options.show_timestamps.unwrap_or(true),
+            header,
+        })?;
+    }
+    let is_animation = preview_type == "gif" || preview_type == "webp";
+    if preview_type == "clip" || preview_type == "highlights" || is_animation {
+        if let Some(watermark) = settings::resolve_watermark(&app, options.watermark.as_ref(), options.preset.as_deref())? {
+            watermark::apply_watermark(&mut graph, &watermark)?;
+        }
+    }
+    let fps = options.fps.unwrap_or(animation::DEFAULT_FPS);
+    let width = options.width.unwrap_or(animation::DEFAULT_WIDTH);
+    match preview_type.as_str() {
+        "gif" => animation::apply_gif_palette(&mut graph, fps, width, options.dither.as_deref())?,
+        "webp" => animation::apply_webp_scale(&mut graph, fps, width)?,
+        _ => {}
+    }
+
+    let mut args = Vec::new();
+    if matches!(preview_type.as_str(), "thumbnail" | "best_frame" | "clip" | "gif" | "webp") {
+        // Seek on the input so long files don't have to be decoded up to the timestamp
+        args.push("-ss".to_string());
+        args.push(timestamp_str);
+    }
+    if preview_type == "clip" || is_animation {
+        let (default_duration, max_duration) = if is_animation {
+            (animation::DEFAULT_DURATION, 60.0)
+        } else {
+            (5.0, 600.0)
+        };
+        let duration = options.duration.unwrap_or(default_duration);
+        if duration <= 0.0 || duration > max_duration {
+            return Err(format!("Preview duration must be between 0 and {} seconds", max_duration));
+        }
+        args.push("-t".to_string());
+        args.push(duration.to_string());
+        expected_duration = Some(duration);
+    }
+    args.extend(graph.input_args(&options.input_path));
+    args.extend(graph.output_args());
+
+    match preview_type.as_str() {
+        "best_frame" => {
+            args.extend(vec![
+                "-frames:v".to_string(),
+                "1".to_string(),
+                "-q:v".to_string(),
+                "2".to_string(),
+            ]);
+        }
+        "contact_sheet" => {
+            let format = contact_sheet::image_format(options.image_format.as_deref(), &options.output_path)?;
+            output_file = contact_sheet::output_path_for(&options.output_path, &format);
+            args.extend(vec![
+                "-frames:v".to_string(),
+                "1".to_string(),
+                "-an".to_string(),
+            ]);
+            args.extend(contact_sheet::image_codec_args(&format));
+        }
+        "gif" | "webp" => {
+            args.extend(animation::output_args(&preview_type, options.loop_count.unwrap_or(0)));
+        }
         "thumbnail" => {
             // Extract single frame
             args.extend(vec![
-                "-ss".to_string(),
-                timestamp_str,
                 "-vframes".to_string(),
                 "1".to_string(),
                 "-q:v".to_string(),
                 "2".to_string(), // High quality
             ]);
         }
-        "clip" => {
-            // Extract 5-second clip
+        "clip" | "highlights" => {
             args.extend(vec![
-                "-ss".to_string(),
-                timestamp_str,
-                "-t".to_string(),
-                "5".to_string(),
                 "-c:v".to_string(),
                 "libx264".to_string(),
                 "-c:a".to_string(),
@@ -69,12 +284,12 @@ pub async fn generate_preview(
             ]);
         }
         _ => {
-            return Err("Invalid preview type. Use 'thumbnail' or 'clip'".to_string());
+            return Err("Invalid preview type. Use 'thumbnail', 'best_frame', 'clip', 'highlights', 'contact_sheet', 'gif' or 'webp'".to_string());
         }
     }
 
     args.push("-y".to_string()); // Overwrite output
-    args.push(options.output_path.clone());
+    args.push(output_file.clone());
 
     // Clear any previous process
     {
@@ -83,45 +298,174 @@ pub async fn generate_preview(
     }
 
     // Execute FFmpeg with progress tracking
-    ffmpeg::execute_ffmpeg_with_progress(&app, args, "conversion-progress", process_state.inner().clone())
-        .map_err(|e| e.message)?;
+    ffmpeg::execute_ffmpeg_pass(
+        &app,
+        args,
+        "conversion-progress",
+        process_state.inner().clone(),
+        expected_duration,
+        progress_range,
+    )
+    .map_err(|e| e.message)?;
+
+    let file_size = std::fs::metadata(&output_file)
+        .map(|metadata| metadata.len())
+        .map_err(|e| format!("Failed to read preview file: {}", e))?;
+
+    Ok(PreviewResult {
+        output_path: output_file,
+        file_size,
+    })
+}
+
+/// Render a waveform or spectrogram PNG, optionally comparing two files
+fn generate_audio_image(
+    app: &AppHandle,
+    options: &PreviewOptions,
+    preview_type: &str,
+    media: &MediaInfo,
+    process_state: Arc<Mutex<Option<Child>>>,
+) -> Result<PreviewResult, String> {
+    if media.audio.is_none() {
+        return Err("Input has no audio track".to_string());
+    }
+    if let Some(compare_path) = options.compare_path.as_deref() {
+        if !PathBuf::from(compare_path).exists() {
+            return Err("Comparison file does not exist".to_string());
+        }
+        let compare_media = probe::probe_media(app, compare_path).map_err(|e| e.message)?;
+        if compare_media.audio.is_none() {
+            return Err("Comparison file has no audio track".to_string());
+        }
+    }
+
+    let is_waveform = preview_type == "waveform";
+    let style = AudioImageStyle {
+        width: options.width.unwrap_or(audio_image::DEFAULT_WIDTH),
+        height: options.height.unwrap_or(if is_waveform {
+            audio_image::DEFAULT_WAVEFORM_HEIGHT
+        } else {
+            audio_image::DEFAULT_SPECTROGRAM_HEIGHT
+        }),
+        color: options.color.clone(),
+        split_channels: options.split_channels.unwrap_or(false),
+        scale: options.scale.clone(),
+    };
+    let filter = if is_waveform {
+        audio_image::waveform_filter(&style)?
+    } else {
+        audio_image::spectrogram_filter(&style)?
+    };
+
+    let output_file = contact_sheet::output_path_for(&options.output_path, "png");
+    let args = audio_image::image_args(
+        &options.input_path,
+        options.compare_path.as_deref(),
+        &filter,
+        &output_file,
+    );
+
+    ffmpeg::execute_ffmpeg_pass(
+        app,
+        args,
+        "conversion-progress",
+        process_state,
+        media.duration,
+        (0.0, 100.0),
+    )
+    .map_err(|e| e.message)?;
 
-    Ok(options.output_path)
+    let file_size = std::fs::metadata(&output_file)
+        .map(|metadata| metadata.len())
+        .map_err(|e| format!("Failed to read preview file: {}", e))?;
+
+    Ok(PreviewResult {
+        output_path: output_file,
+        file_size,
+    })
+}
+
+/// Smallest and largest width of a downscaled thumbnail variant
+const MIN_VARIANT_WIDTH: u32 = 16;
+const MAX_VARIANT_WIDTH: u32 = 4096;
+
+/// Stored with cached thumbnail variants so they are redone when the preview changes
+#[derive(Debug, Serialize, Deserialize)]
+struct VariantManifest {
+    source: SourceStamp,
 }
 
-/// Read preview file and return as base64 data URL
+/// Describe a preview file for loading through the media protocol. With `max_width`,
+/// images are replaced by a cached JPEG at most that wide (e.g. for list views).
 #[tauri::command]
-pub async fn read_preview_file(file_path: String) -> Result<String, String> {
-    use std::fs;
-    use base64::{Engine, engine::general_purpose};
-    
+pub async fn read_preview_file(
+    app: AppHandle,
+    file_path: String,
+    max_width: Option<u32>,
+    media_access: State<'_, MediaAccess>,
+) -> Result<MediaFile, String> {
     let path = PathBuf::from(&file_path);
     if !path.exists() {
         return Err("File does not exist".to_string());
     }
 
-    let file_data = fs::read(&path)
-        .map_err(|e| format!("Failed to read file: {}", e))?;
-
-    // Determine MIME type from file extension
-    let mime_type = if file_path.to_lowercase().ends_with(".jpg") || file_path.to_lowercase().ends_with(".jpeg") {
-        "image/jpeg"
-    } else if file_path.to_lowercase().ends_with(".png") {
-        "image/png"
-    } else if file_path.to_lowercase().ends_with(".gif") {
-        "image/gif"
-    } else if file_path.to_lowercase().ends_with(".webp") {
-        "image/webp"
-    } else if file_path.to_lowercase().ends_with(".mp4") {
-        "video/mp4"
-    } else {
-        "application/octet-stream"
-    };
+    let is_image = media_protocol::content_type(&path).starts_with("image/");
+    match max_width {
+        Some(max_width) if is_image => {
+            if !(MIN_VARIANT_WIDTH..=MAX_VARIANT_WIDTH).contains(&max_width) {
+                return Err(format!(
+                    "Thumbnail width must be between {} and {} pixels",
+                    MIN_VARIANT_WIDTH, MAX_VARIANT_WIDTH
+                ));
+            }
+            let variant = thumbnail_variant(&app, &path, max_width)?;
+            media_protocol::media_file(&media_access, &variant)
+        }
+        _ => media_protocol::media_file(&media_access, &path),
+    }
+}
+
+/// Downscale an image into the workspace cache, reusing an earlier result while
+/// the source is unchanged
+fn thumbnail_variant(app: &AppHandle, path: &Path, max_width: u32) -> Result<PathBuf, String> {
+    let workspace = settings::workspace_dir(app)?;
+    let cache_dir = cache::cache_dir(&workspace, "thumbnails", path);
+    let variant = cache_dir.join(format!("w{}.jpg", max_width));
+    let stamp = SourceStamp::read(path)?;
 
-    // Convert to base64
-    let base64 = general_purpose::STANDARD.encode(&file_data);
-    let data_url = format!("data:{};base64,{}", mime_type, base64);
+    match cache::read_manifest::<VariantManifest>(&cache_dir) {
+        Some(manifest) if manifest.source == stamp => {
+            if variant.exists() {
+                return Ok(variant);
+            }
+        }
+        // Variants of an older version of the preview, at any width, are stale
+        _ => {
+            cache::reset_dir(&cache_dir)?;
+            cache::write_manifest(&cache_dir, &VariantManifest { source: stamp })?;
+        }
+    }
+
+    let args = vec![
+        "-v".to_string(),
+        "error".to_string(),
+        "-i".to_string(),
+        path.to_string_lossy().to_string(),
+        "-vf".to_string(),
+        // Never upscale; keep the height even for the JPEG encoder
+        format!("scale=min(iw\\,{}):-2", max_width),
+        "-frames:v".to_string(),
+        "1".to_string(),
+        "-q:v".to_string(),
+        "3".to_string(),
+        "-y".to_string(),
+        variant.to_string_lossy().to_string(),
+    ];
+    let output = ffmpeg::read_ffmpeg_output(app, args).map_err(|e| e.message)?;
+    if !variant.exists() {
+        return Err(format!("Failed to create thumbnail: {}", output.trim()));
+    }
 
-    Ok(data_url)
+    Ok(variant)
 }
 
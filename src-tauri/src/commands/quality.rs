@@ -0,0 +1,88 @@
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::process::Child;
+use tauri::{AppHandle, State, Emitter};
+use crate::utils::ffmpeg;
+use crate::utils::probe;
+use crate::utils::quality::{self, QualityJob, QualityReport};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct QualityOptions {
+    pub reference_path: String, // e.g. the source of convert_video
+    pub distorted_path: String, // e.g. its output
+    pub vmaf: Option<bool>, // Default: when FFmpeg has libvmaf; true fails without it
+}
+
+/// Measure PSNR, SSIM and (if available) VMAF of a file against its reference.
+/// The distorted file is scaled to the reference's resolution and frame rate.
+#[tauri::command]
+pub async fn compare_quality(
+    app: AppHandle,
+    options: QualityOptions,
+    process_state: State<'_, Arc<Mutex<Option<Child>>>>,
+) -> Result<QualityReport, String> {
+    if !PathBuf::from(&options.reference_path).exists() {
+        return Err("Reference file does not exist".to_string());
+    }
+    if !PathBuf::from(&options.distorted_path).exists() {
+        return Err("Distorted file does not exist".to_string());
+    }
+
+    let has_vmaf = ffmpeg::has_filter(&app, "libvmaf").map_err(|e| e.message)?;
+    let vmaf = match options.vmaf {
+        Some(true) if !has_vmaf => {
+            return Err("This FFmpeg build does not include libvmaf".to_string());
+        }
+        Some(requested) => requested,
+        None => has_vmaf,
+    };
+
+    let reference = probe::probe_media(&app, &options.reference_path).map_err(|e| e.message)?;
+    let distorted = probe::probe_media(&app, &options.distorted_path).map_err(|e| e.message)?;
+    let video = reference
+        .video
+        .as_ref()
+        .ok_or_else(|| "Reference has no video track".to_string())?;
+    if distorted.video.is_none() {
+        return Err("Distorted file has no video track".to_string());
+    }
+
+    // Frames are auto-rotated, so compare at the displayed size
+    let (width, height) = if video.rotation % 180 == 90 {
+        (video.height, video.width)
+    } else {
+        (video.width, video.height)
+    };
+
+    let temp_dir = ffmpeg::job_temp_dir("quality");
+
+    // Clear any previous process
+    {
+        let mut state = process_state.lock().unwrap();
+        *state = None;
+    }
+
+    let job = QualityJob {
+        reference: &options.reference_path,
+        reference_options: Vec::new(),
+        distorted: &options.distorted_path,
+        distorted_options: Vec::new(),
+        width,
+        height,
+        fps: video.fps,
+        vmaf,
+        work_dir: &temp_dir,
+    };
+    let duration = match (reference.duration, distorted.duration) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    };
+    let result = quality::measure_quality(&app, &job, process_state.inner().clone(), duration, (0.0, 100.0));
+    let _ = std::fs::remove_dir_all(&temp_dir);
+    let report = result.map_err(|e| format!("Failed to measure quality: {}", e.message))?;
+
+    app.emit("conversion-progress", 100.0).ok();
+
+    Ok(report)
+}
@@ -0,0 +1,292 @@
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::process::Child;
+use tauri::{AppHandle, State, Emitter};
+use crate::utils::ffmpeg;
+use crate::utils::probe;
+use crate::utils::scene::{self, Chapter, SceneChange};
+
+const DEFAULT_THRESHOLD: f64 = 0.3;
+const DEFAULT_MIN_SCENE_LENGTH: f64 = 1.0;
+const DEFAULT_MIN_CHAPTER_LENGTH: f64 = 10.0;
+const DEFAULT_MAX_THUMBNAILS: usize = 50;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SceneOptions {
+    pub input_path: String,
+    pub threshold: Option<f64>, // Scene score needed to count as a cut, 0-1, default 0.3
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SceneThumbnailOptions {
+    pub input_path: String,
+    pub output_dir: String,
+    pub threshold: Option<f64>,
+    pub min_scene_length: Option<f64>, // Seconds, shorter scenes are merged into the previous one (default 1.0)
+    pub max_thumbnails: Option<usize>, // Default 50
+    pub width: Option<u32>, // Thumbnail width in pixels, keeps aspect ratio
+}
+
+#[derive(Debug, Serialize)]
+pub struct SceneThumbnail {
+    pub time: f64, // Where the frame was taken
+    pub scene_start: f64,
+    pub path: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ChapterOptions {
+    pub input_path: String,
+    pub output_path: Option<String>, // When set, the chapters are written into a copy of the input
+    pub threshold: Option<f64>,
+    pub min_chapter_length: Option<f64>, // Seconds, default 10
+}
+
+/// Find scene changes with their timestamps and scores
+#[tauri::command]
+pub async fn detect_scenes(
+    app: AppHandle,
+    options: SceneOptions,
+    process_state: State<'_, Arc<Mutex<Option<Child>>>>,
+) -> Result<Vec<SceneChange>, String> {
+    let input_path = PathBuf::from(&options.input_path);
+    if !input_path.exists() {
+        return Err("Input file does not exist".to_string());
+    }
+
+    let media = probe::probe_media(&app, &options.input_path).map_err(|e| e.message)?;
+    if media.video.is_none() {
+        return Err("Input has no video track".to_string());
+    }
+
+    // Clear any previous process
+    {
+        let mut state = process_state.lock().unwrap();
+        *state = None;
+    }
+
+    let scenes = scene::detect_scenes(
+        &app,
+        &options.input_path,
+        options.threshold.unwrap_or(DEFAULT_THRESHOLD),
+        process_state.inner().clone(),
+        media.duration,
+        (0.0, 100.0),
+    )
+    .map_err(|e| e.message)?;
+
+    app.emit("conversion-progress", 100.0).ok();
+
+    Ok(scenes)
+}
+
+/// Extract one thumbnail from the middle of each scene
+#[tauri::command]
+pub async fn generate_scene_thumbnails(
+    app: AppHandle,
+    options: SceneThumbnailOptions,
+    process_state: State<'_, Arc<Mutex<Option<Child>>>>,
+) -> Result<Vec<SceneThumbnail>, String> {
+    let input_path = PathBuf::from(&options.input_path);
+    if !input_path.exists() {
+        return Err("Input file does not exist".to_string());
+    }
+
+    let output_dir = PathBuf::from(&options.output_dir);
+    std::fs::create_dir_all(&output_dir)
+        .map_err(|e| format!("Failed to create output directory: {}", e))?;
+
+    let media = probe::probe_media(&app, &options.input_path).map_err(|e| e.message)?;
+    if media.video.is_none() {
+        return Err("Input has no video track".to_string());
+    }
+    let duration = media
+        .duration
+        .ok_or_else(|| "Could not determine the input duration".to_string())?;
+
+    let base_name = input_path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("video")
+        .to_string();
+
+    // Clear any previous process
+    {
+        let mut state = process_state.lock().unwrap();
+        *state = None;
+    }
+
+    // Step 1: Detect scenes (0-60%)
+    let scenes = scene::detect_scenes(
+        &app,
+        &options.input_path,
+        options.threshold.unwrap_or(DEFAULT_THRESHOLD),
+        process_state.inner().clone(),
+        Some(duration),
+        (0.0, 60.0),
+    )
+    .map_err(|e| e.message)?;
+
+    let mut starts = scene::scene_starts(&scenes, options.min_scene_length.unwrap_or(DEFAULT_MIN_SCENE_LENGTH));
+    starts.retain(|start| *start < duration);
+    starts.truncate(options.max_thumbnails.unwrap_or(DEFAULT_MAX_THUMBNAILS).max(1));
+
+    // Step 2: Grab a frame from the middle of each scene (60-100%)
+    let mut thumbnails = Vec::new();
+    for (index, start) in starts.iter().enumerate() {
+        let end = starts.get(index + 1).copied().unwrap_or(duration);
+        let time = start + (end - start) / 2.0;
+        let path = output_dir.join(format!("{}_scene_{:03}.jpg", base_name, index + 1));
+
+        let mut args = vec![
+            "-ss".to_string(),
+            format!("{:.3}", time),
+            "-i".to_string(),
+            options.input_path.clone(),
+        ];
+        if let Some(width) = options.width {
+            args.push("-vf".to_string());
+            args.push(format!("scale={}:-2", width));
+        }
+        args.extend(vec![
+            "-frames:v".to_string(),
+            "1".to_string(),
+            "-q:v".to_string(),
+            "2".to_string(),
+            "-y".to_string(),
+            path.to_string_lossy().to_string(),
+        ]);
+
+        // Clear process state for the next extraction
+        {
+            let mut state = process_state.lock().unwrap();
+            *state = None;
+        }
+
+        let progress_start = 60.0 + 40.0 * index as f64 / starts.len() as f64;
+        let progress_end = 60.0 + 40.0 * (index + 1) as f64 / starts.len() as f64;
+        ffmpeg::execute_ffmpeg_pass(
+            &app,
+            args,
+            "conversion-progress",
+            process_state.inner().clone(),
+            None,
+            (progress_start, progress_end),
+        )
+        .map_err(|e| format!("Failed to extract thumbnail {}: {}", index + 1, e.message))?;
+
+        thumbnails.push(SceneThumbnail {
+            time,
+            scene_start: *start,
+            path: path.to_string_lossy().to_string(),
+        });
+    }
+
+    app.emit("conversion-progress", 100.0).ok();
+
+    Ok(thumbnails)
+}
+
+/// Build chapters from scene changes, optionally embedding them into a copy of the input
+#[tauri::command]
+pub async fn generate_scene_chapters(
+    app: AppHandle,
+    options: ChapterOptions,
+    process_state: State<'_, Arc<Mutex<Option<Child>>>>,
+) -> Result<Vec<Chapter>, String> {
+    let input_path = PathBuf::from(&options.input_path);
+    if !input_path.exists() {
+        return Err("Input file does not exist".to_string());
+    }
+
+    let media = probe::probe_media(&app, &options.input_path).map_err(|e| e.message)?;
+    if media.video.is_none() {
+        return Err("Input has no video track".to_string());
+    }
+    let duration = media
+        .duration
+        .ok_or_else(|| "Could not determine the input duration".to_string())?;
+
+    // Clear any previous process
+    {
+        let mut state = process_state.lock().unwrap();
+        *state = None;
+    }
+
+    let analysis_end = if options.output_path.is_some() { 80.0 } else { 100.0 };
+    let scenes = scene::detect_scenes(
+        &app,
+        &options.input_path,
+        options.threshold.unwrap_or(DEFAULT_THRESHOLD),
+        process_state.inner().clone(),
+        Some(duration),
+        (0.0, analysis_end),
+    )
+    .map_err(|e| e.message)?;
+
+    let chapters = scene::scene_chapters(
+        &scenes,
+        duration,
+        options.min_chapter_length.unwrap_or(DEFAULT_MIN_CHAPTER_LENGTH),
+    );
+
+    if let Some(output_path) = options.output_path.as_deref() {
+        let output_dir = PathBuf::from(output_path)
+            .parent()
+            .ok_or_else(|| "Invalid output path".to_string())?
+            .to_path_buf();
+
+        // Create output directory if it doesn't exist
+        std::fs::create_dir_all(&output_dir)
+            .map_err(|e| format!("Failed to create output directory: {}", e))?;
+
+        let temp_dir = ffmpeg::job_temp_dir("chapters");
+        std::fs::create_dir_all(&temp_dir)
+            .map_err(|e| format!("Failed to create temp directory: {}", e))?;
+        let metadata_file = temp_dir.join("chapters.txt");
+        std::fs::write(&metadata_file, scene::chapters_metadata(&chapters))
+            .map_err(|e| format!("Failed to write chapter metadata: {}", e))?;
+
+        // Stream copy, taking chapters from the metadata file and everything else from the input
+        let args = vec![
+            "-i".to_string(),
+            options.input_path.clone(),
+            "-f".to_string(),
+            "ffmetadata".to_string(),
+            "-i".to_string(),
+            metadata_file.to_string_lossy().to_string(),
+            "-map".to_string(),
+            "0".to_string(),
+            "-map_metadata".to_string(),
+            "0".to_string(),
+            "-map_chapters".to_string(),
+            "1".to_string(),
+            "-c".to_string(),
+            "copy".to_string(),
+            "-y".to_string(),
+            output_path.to_string(),
+        ];
+
+        // Clear process state for the remux
+        {
+            let mut state = process_state.lock().unwrap();
+            *state = None;
+        }
+
+        let result = ffmpeg::execute_ffmpeg_pass(
+            &app,
+            args,
+            "conversion-progress",
+            process_state.inner().clone(),
+            Some(duration),
+            (analysis_end, 100.0),
+        );
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        result.map_err(|e| format!("Failed to write chapters: {}", e.message))?;
+    }
+
+    app.emit("conversion-progress", 100.0).ok();
+
+    Ok(chapters)
+}
@@ -2,10 +2,19 @@ use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::PathBuf;
 use tauri::{AppHandle, Manager};
+use crate::utils::watermark::WatermarkOptions;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ConversionPreset {
+    pub name: String,
+    pub watermark: Option<WatermarkOptions>,
+}
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct AppSettings {
     pub workspace_path: String,
+    #[serde(default)]
+    pub presets: Vec<ConversionPreset>,
 }
 
 impl Default for AppSettings {
@@ -17,6 +26,7 @@ impl Default for AppSettings {
         
         AppSettings {
             workspace_path: default_path,
+            presets: Vec::new(),
         }
     }
 }
@@ -35,10 +45,9 @@ fn get_settings_path(app: &AppHandle) -> Result<PathBuf, String> {
     Ok(settings_dir.join("settings.json"))
 }
 
-/// Load settings from file
-#[tauri::command]
-pub async fn load_settings(app: AppHandle) -> Result<AppSettings, String> {
-    let settings_path = get_settings_path(&app)?;
+/// Read settings from disk, falling back to defaults when no file exists yet
+pub fn read_settings(app: &AppHandle) -> Result<AppSettings, String> {
+    let settings_path = get_settings_path(app)?;
     
     if !settings_path.exists() {
         // Return default settings if file doesn't exist
@@ -54,6 +63,47 @@ pub async fn load_settings(app: AppHandle) -> Result<AppSettings, String> {
     Ok(settings)
 }
 
+/// Workspace folder from the settings, created if missing
+pub fn workspace_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let settings = read_settings(app)?;
+    if settings.workspace_path.is_empty() {
+        return Err("No workspace folder is configured".to_string());
+    }
+
+    let workspace_path = PathBuf::from(&settings.workspace_path);
+    fs::create_dir_all(&workspace_path)
+        .map_err(|e| format!("Failed to create workspace directory: {}", e))?;
+    Ok(workspace_path)
+}
+
+/// Look up a conversion preset by name
+pub fn find_preset(app: &AppHandle, name: &str) -> Result<ConversionPreset, String> {
+    read_settings(app)?
+        .presets
+        .into_iter()
+        .find(|preset| preset.name == name)
+        .ok_or_else(|| format!("Preset not found: {}", name))
+}
+
+/// Use the explicit watermark if given, otherwise the one from the named preset
+pub fn resolve_watermark(
+    app: &AppHandle,
+    watermark: Option<&WatermarkOptions>,
+    preset: Option<&str>,
+) -> Result<Option<WatermarkOptions>, String> {
+    match (watermark, preset) {
+        (Some(watermark), _) => Ok(Some(watermark.clone())),
+        (None, Some(name)) => Ok(find_preset(app, name)?.watermark),
+        (None, None) => Ok(None),
+    }
+}
+
+/// Load settings from file
+#[tauri::command]
+pub async fn load_settings(app: AppHandle) -> Result<AppSettings, String> {
+    read_settings(&app)
+}
+
 /// Save settings to file
 #[tauri::command]
 pub async fn save_settings(app: AppHandle, settings: AppSettings) -> Result<(), String> {
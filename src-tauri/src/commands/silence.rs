@@ -0,0 +1,218 @@
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::process::Child;
+use tauri::{AppHandle, State, Emitter};
+use crate::utils::ffmpeg;
+use crate::utils::probe;
+use crate::utils::silence::{self, TimeSpan};
+
+const DEFAULT_NOISE_DB: f64 = -30.0;
+const DEFAULT_MIN_DURATION: f64 = 1.0;
+const DEFAULT_PADDING: f64 = 0.25;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SilenceOptions {
+    pub input_path: String,
+    pub noise_db: Option<f64>, // Anything quieter counts as silence, default -30 dB
+    pub min_duration: Option<f64>, // Shortest silence to report in seconds, default 1.0
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RemoveSilenceOptions {
+    pub input_path: String,
+    pub output_path: String,
+    pub noise_db: Option<f64>,
+    pub min_duration: Option<f64>,
+    pub padding: Option<f64>, // Seconds kept around each non-silent segment, default 0.25
+}
+
+#[derive(Debug, Serialize)]
+pub struct RemoveSilenceResult {
+    pub output_path: String,
+    pub original_duration: f64,
+    pub removed_duration: f64, // Seconds cut from the original
+    pub kept_segments: Vec<TimeSpan>,
+}
+
+/// Find silent ranges in the audio track
+#[tauri::command]
+pub async fn detect_silence(
+    app: AppHandle,
+    options: SilenceOptions,
+    process_state: State<'_, Arc<Mutex<Option<Child>>>>,
+) -> Result<Vec<TimeSpan>, String> {
+    let input_path = PathBuf::from(&options.input_path);
+    if !input_path.exists() {
+        return Err("Input file does not exist".to_string());
+    }
+
+    let media = probe::probe_media(&app, &options.input_path).map_err(|e| e.message)?;
+    if media.audio.is_none() {
+        return Err("Input has no audio track".to_string());
+    }
+
+    // Clear any previous process
+    {
+        let mut state = process_state.lock().unwrap();
+        *state = None;
+    }
+
+    let ranges = run_silence_detection(
+        &app,
+        &options.input_path,
+        options.noise_db.unwrap_or(DEFAULT_NOISE_DB),
+        options.min_duration.unwrap_or(DEFAULT_MIN_DURATION),
+        media.duration,
+        process_state.inner().clone(),
+        (0.0, 100.0),
+    )?;
+
+    app.emit("conversion-progress", 100.0).ok();
+
+    Ok(ranges)
+}
+
+/// Cut silent stretches out of a recording and render the remaining segments
+#[tauri::command]
+pub async fn remove_silence(
+    app: AppHandle,
+    options: RemoveSilenceOptions,
+    process_state: State<'_, Arc<Mutex<Option<Child>>>>,
+) -> Result<RemoveSilenceResult, String> {
+    let input_path = PathBuf::from(&options.input_path);
+    if !input_path.exists() {
+        return Err("Input file does not exist".to_string());
+    }
+
+    let output_path = PathBuf::from(&options.output_path);
+    let output_dir = output_path
+        .parent()
+        .ok_or_else(|| "Invalid output path".to_string())?;
+
+    // Create output directory if it doesn't exist
+    std::fs::create_dir_all(output_dir)
+        .map_err(|e| format!("Failed to create output directory: {}", e))?;
+
+    let media = probe::probe_media(&app, &options.input_path).map_err(|e| e.message)?;
+    if media.audio.is_none() {
+        return Err("Input has no audio track".to_string());
+    }
+    let duration = media
+        .duration
+        .ok_or_else(|| "Could not determine the input duration".to_string())?;
+
+    let padding = options.padding.unwrap_or(DEFAULT_PADDING);
+    if padding < 0.0 {
+        return Err("Padding cannot be negative".to_string());
+    }
+
+    // Clear any previous process
+    {
+        let mut state = process_state.lock().unwrap();
+        *state = None;
+    }
+
+    // Step 1: Detect silence (0-30%)
+    let silences = run_silence_detection(
+        &app,
+        &options.input_path,
+        options.noise_db.unwrap_or(DEFAULT_NOISE_DB),
+        options.min_duration.unwrap_or(DEFAULT_MIN_DURATION),
+        media.duration,
+        process_state.inner().clone(),
+        (0.0, 30.0),
+    )?;
+
+    let keep = silence::keep_ranges(&silences, duration, padding);
+    if keep.is_empty() {
+        return Err("The whole file is silent".to_string());
+    }
+    let kept_duration: f64 = keep.iter().map(|span| span.duration()).sum();
+
+    // Step 2: Render only the kept segments (30-100%)
+    // The graph grows with every segment, so it goes into a script file
+    let has_video = media.video.is_some();
+    let temp_dir = ffmpeg::job_temp_dir("silence");
+    std::fs::create_dir_all(&temp_dir)
+        .map_err(|e| format!("Failed to create temp directory: {}", e))?;
+    let script_file = temp_dir.join("segments.txt");
+    std::fs::write(&script_file, silence::concat_graph(&keep, has_video))
+        .map_err(|e| format!("Failed to write filter script: {}", e))?;
+
+    let mut args = vec![
+        "-i".to_string(),
+        options.input_path.clone(),
+        "-filter_complex_script".to_string(),
+        script_file.to_string_lossy().to_string(),
+    ];
+    if has_video {
+        args.push("-map".to_string());
+        args.push("[v]".to_string());
+    }
+    args.push("-map".to_string());
+    args.push("[a]".to_string());
+    args.extend(ffmpeg::h264_aac_args());
+    args.push("-y".to_string());
+    args.push(options.output_path.clone());
+
+    // Clear process state for the render pass
+    {
+        let mut state = process_state.lock().unwrap();
+        *state = None;
+    }
+
+    let result = ffmpeg::execute_ffmpeg_pass(
+        &app,
+        args,
+        "conversion-progress",
+        process_state.inner().clone(),
+        Some(kept_duration),
+        (30.0, 100.0),
+    );
+    let _ = std::fs::remove_dir_all(&temp_dir);
+    result.map_err(|e| e.message)?;
+
+    app.emit("conversion-progress", 100.0).ok();
+
+    Ok(RemoveSilenceResult {
+        output_path: options.output_path,
+        original_duration: duration,
+        removed_duration: (duration - kept_duration).max(0.0),
+        kept_segments: keep,
+    })
+}
+
+fn run_silence_detection(
+    app: &AppHandle,
+    input_path: &str,
+    noise_db: f64,
+    min_duration: f64,
+    duration: Option<f64>,
+    process_state: Arc<Mutex<Option<Child>>>,
+    progress_range: (f64, f64),
+) -> Result<Vec<TimeSpan>, String> {
+    let args = vec![
+        "-hide_banner".to_string(),
+        "-i".to_string(),
+        input_path.to_string(),
+        "-vn".to_string(),
+        "-af".to_string(),
+        silence::silencedetect_filter(noise_db, min_duration)?,
+        "-f".to_string(),
+        "null".to_string(),
+        "-".to_string(),
+    ];
+
+    let log = ffmpeg::execute_ffmpeg_capture(
+        app,
+        args,
+        "conversion-progress",
+        process_state,
+        duration,
+        progress_range,
+    )
+    .map_err(|e| format!("Failed to detect silence: {}", e.message))?;
+
+    Ok(silence::parse_silence_output(&log, duration))
+}
@@ -0,0 +1,165 @@
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::process::Child;
+use tauri::{AppHandle, State, Emitter};
+use crate::commands::settings;
+use crate::utils::cache::{self, SourceStamp};
+use crate::utils::ffmpeg;
+use crate::utils::probe;
+use crate::utils::sprites::{self, SpriteLayout, SpriteManifest};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SpriteOptions {
+    pub input_path: String,
+    pub interval: Option<f64>, // Seconds between frames, default 2
+    pub tile_width: Option<u32>, // Default 160
+    pub columns: Option<u32>, // Frames per sheet row, default 10
+    pub rows: Option<u32>, // Rows per sheet, default 10
+}
+
+#[derive(Debug, Serialize)]
+pub struct SpriteResult {
+    pub vtt_path: String,
+    pub sprite_paths: Vec<String>,
+    pub layout: SpriteLayout,
+    pub cached: bool, // True when existing sprites were reused
+}
+
+/// Generate scrubbing sprite sheets and a WebVTT thumbnail track, cached in the workspace
+#[tauri::command]
+pub async fn generate_sprites(
+    app: AppHandle,
+    options: SpriteOptions,
+    process_state: State<'_, Arc<Mutex<Option<Child>>>>,
+) -> Result<SpriteResult, String> {
+    let input_path = PathBuf::from(&options.input_path);
+    if !input_path.exists() {
+        return Err("Input file does not exist".to_string());
+    }
+
+    let interval = options.interval.unwrap_or(2.0);
+    if !(0.1..=600.0).contains(&interval) {
+        return Err("Sprite interval must be between 0.1 and 600 seconds".to_string());
+    }
+    let tile_width = options.tile_width.unwrap_or(160);
+    if !(32..=640).contains(&tile_width) {
+        return Err("Sprite tile width must be between 32 and 640 pixels".to_string());
+    }
+    let columns = options.columns.unwrap_or(10);
+    let rows = options.rows.unwrap_or(10);
+    if columns == 0 || rows == 0 || columns > 20 || rows > 20 {
+        return Err("Sprite sheets must have between 1 and 20 columns and rows".to_string());
+    }
+
+    let media = probe::probe_media(&app, &options.input_path).map_err(|e| e.message)?;
+    let video = media
+        .video
+        .as_ref()
+        .ok_or_else(|| "Input has no video track".to_string())?;
+    let duration = media
+        .duration
+        .ok_or_else(|| "Could not determine the input duration".to_string())?;
+
+    // Frames are auto-rotated, so the tiles follow the displayed orientation
+    let (width, height) = if video.rotation % 180 == 90 {
+        (video.height, video.width)
+    } else {
+        (video.width, video.height)
+    };
+    let layout = SpriteLayout {
+        interval,
+        tile_width,
+        tile_height: sprites::tile_height(tile_width, width, height),
+        columns,
+        rows,
+    };
+
+    let workspace = settings::workspace_dir(&app)?;
+    let cache_dir = cache::cache_dir(&workspace, "sprites", &input_path);
+    let stamp = SourceStamp::read(&input_path)?;
+
+    // Reuse the sprites when neither the source nor the layout changed
+    if let Some(manifest) = cache::read_manifest::<SpriteManifest>(&cache_dir) {
+        let files_exist = manifest.sprite_files.iter().all(|file| cache_dir.join(file).exists())
+            && cache_dir.join(&manifest.vtt_file).exists();
+        if manifest.source == stamp && manifest.layout == layout && files_exist {
+            return Ok(SpriteResult {
+                vtt_path: cache_dir.join(&manifest.vtt_file).to_string_lossy().to_string(),
+                sprite_paths: manifest
+                    .sprite_files
+                    .iter()
+                    .map(|file| cache_dir.join(file).to_string_lossy().to_string())
+                    .collect(),
+                layout,
+                cached: true,
+            });
+        }
+    }
+
+    cache::reset_dir(&cache_dir)?;
+
+    let args = vec![
+        "-i".to_string(),
+        options.input_path.clone(),
+        "-an".to_string(),
+        "-sn".to_string(),
+        "-vf".to_string(),
+        layout.filter(),
+        "-q:v".to_string(),
+        "4".to_string(),
+        "-y".to_string(),
+        cache_dir.join("sprite_%03d.jpg").to_string_lossy().to_string(),
+    ];
+
+    // Clear any previous process
+    {
+        let mut state = process_state.lock().unwrap();
+        *state = None;
+    }
+
+    if let Err(e) = ffmpeg::execute_ffmpeg_pass(
+        &app,
+        args,
+        "conversion-progress",
+        process_state.inner().clone(),
+        Some(duration),
+        (0.0, 100.0),
+    ) {
+        let _ = std::fs::remove_dir_all(&cache_dir);
+        return Err(format!("Failed to generate sprites: {}", e.message));
+    }
+
+    let sprite_files: Vec<String> = (1..=layout.sheet_count(duration))
+        .map(sprites::sheet_file_name)
+        .filter(|file| cache_dir.join(file).exists())
+        .collect();
+    if sprite_files.is_empty() {
+        let _ = std::fs::remove_dir_all(&cache_dir);
+        return Err("FFmpeg did not produce any sprite sheets".to_string());
+    }
+
+    let vtt_file = "thumbnails.vtt".to_string();
+    std::fs::write(cache_dir.join(&vtt_file), sprites::build_vtt(&layout, duration))
+        .map_err(|e| format!("Failed to write thumbnail track: {}", e))?;
+
+    let manifest = SpriteManifest {
+        source: stamp,
+        layout: layout.clone(),
+        sprite_files: sprite_files.clone(),
+        vtt_file: vtt_file.clone(),
+    };
+    cache::write_manifest(&cache_dir, &manifest)?;
+
+    app.emit("conversion-progress", 100.0).ok();
+
+    Ok(SpriteResult {
+        vtt_path: cache_dir.join(&vtt_file).to_string_lossy().to_string(),
+        sprite_paths: sprite_files
+            .iter()
+            .map(|file| cache_dir.join(file).to_string_lossy().to_string())
+            .collect(),
+        layout,
+        cached: false,
+    })
+}
@@ -0,0 +1,174 @@
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::process::Child;
+use tauri::{AppHandle, State, Emitter};
+use crate::utils::ffmpeg;
+use crate::utils::filter_graph;
+use crate::utils::probe;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StabilizeOptions {
+    pub input_path: String,
+    pub output_path: String,
+    pub shakiness: Option<u32>, // 1 (little shake) to 10 (very shaky), default 5
+    pub smoothing: Option<u32>, // Frames used to smooth the camera path, 0-1000, default 10
+    pub zoom: Option<f64>, // Extra zoom in percent to hide borders, -100 to 100 (negative zooms out), default 0
+}
+
+/// Stabilize shaky video with vid.stab when available, otherwise with deshake
+#[tauri::command]
+pub async fn stabilize_video(
+    app: AppHandle,
+    options: StabilizeOptions,
+    process_state: State<'_, Arc<Mutex<Option<Child>>>>,
+) -> Result<String, String> {
+    let input_path = PathBuf::from(&options.input_path);
+    if !input_path.exists() {
+        return Err("Input file does not exist".to_string());
+    }
+
+    let output_path = PathBuf::from(&options.output_path);
+    let output_dir = output_path
+        .parent()
+        .ok_or_else(|| "Invalid output path".to_string())?;
+
+    // Create output directory if it doesn't exist
+    std::fs::create_dir_all(output_dir)
+        .map_err(|e| format!("Failed to create output directory: {}", e))?;
+
+    let shakiness = options.shakiness.unwrap_or(5);
+    if !(1..=10).contains(&shakiness) {
+        return Err("Shakiness must be between 1 and 10".to_string());
+    }
+    let smoothing = options.smoothing.unwrap_or(10);
+    if smoothing > 1000 {
+        return Err("Smoothing must be between 0 and 1000 frames".to_string());
+    }
+    let zoom = options.zoom.unwrap_or(0.0);
+    if !(-100.0..=100.0).contains(&zoom) {
+        return Err("Zoom must be between -100 and 100 percent".to_string());
+    }
+
+    let media = probe::probe_media(&app, &options.input_path).map_err(|e| e.message)?;
+    let has_vidstab = ffmpeg::has_filter(&app, "vidstabdetect").map_err(|e| e.message)?
+        && ffmpeg::has_filter(&app, "vidstabtransform").map_err(|e| e.message)?;
+    // The deshake fallback has no equivalent settings, so don't drop them silently
+    if !has_vidstab && (options.smoothing.is_some() || options.zoom.is_some()) {
+        return Err("Smoothing and zoom need an FFmpeg build with vid.stab".to_string());
+    }
+
+    // Clear any previous process
+    {
+        let mut state = process_state.lock().unwrap();
+        *state = None;
+    }
+
+    if !has_vidstab {
+        // Single pass fallback: deshake searches a range that grows with the shakiness
+        let search_range = (shakiness * 6).clamp(8, 64);
+        let mut args = vec![
+            "-i".to_string(),
+            options.input_path.clone(),
+            "-vf".to_string(),
+            format!("deshake=rx={}:ry={}", search_range, search_range),
+        ];
+        args.extend(ffmpeg::h264_aac_args());
+        args.push("-y".to_string());
+        args.push(options.output_path.clone());
+
+        ffmpeg::execute_ffmpeg_with_expected_duration(
+            &app,
+            args,
+            "conversion-progress",
+            process_state.inner().clone(),
+            media.duration,
+        )
+        .map_err(|e| e.message)?;
+
+        return Ok(options.output_path);
+    }
+
+    // The transforms file lives in a temporary directory for this job
+    let temp_dir = ffmpeg::job_temp_dir("stabilize");
+    std::fs::create_dir_all(&temp_dir)
+        .map_err(|e| format!("Failed to create temp directory: {}", e))?;
+    let transforms_file = temp_dir.join("transforms.trf");
+    let transforms_path = filter_graph::escape_filter_path(&transforms_file.to_string_lossy());
+
+    // Pass 1: analyse camera motion (0-40%)
+    let detect_args = vec![
+        "-y".to_string(),
+        "-i".to_string(),
+        options.input_path.clone(),
+        "-vf".to_string(),
+        format!("vidstabdetect=shakiness={}:accuracy=15:result={}", shakiness, transforms_path),
+        "-an".to_string(),
+        "-f".to_string(),
+        "null".to_string(),
+        "-".to_string(),
+    ];
+
+    ffmpeg::execute_ffmpeg_pass(
+        &app,
+        detect_args,
+        "conversion-progress",
+        process_state.inner().clone(),
+        media.duration,
+        (0.0, 40.0),
+    )
+    .map_err(|e| {
+        // Cleanup on error
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        format!("Failed to analyse camera motion: {}", e.message)
+    })?;
+
+    if !transforms_file.exists() {
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        return Err("Stabilization transforms file is missing".to_string());
+    }
+
+    // Clear process state for the transform pass
+    {
+        let mut state = process_state.lock().unwrap();
+        *state = None;
+    }
+
+    // Pass 2: apply the smoothed transforms (40-100%)
+    let mut transform_args = vec![
+        "-i".to_string(),
+        options.input_path.clone(),
+        "-vf".to_string(),
+        format!(
+            "vidstabtransform=input={}:smoothing={}:zoom={}:optzoom=1,unsharp=5:5:0.8:3:3:0.4",
+            transforms_path, smoothing, zoom
+        ),
+    ];
+    transform_args.extend(ffmpeg::h264_aac_args());
+    transform_args.push("-y".to_string());
+    transform_args.push(options.output_path.clone());
+
+    ffmpeg::execute_ffmpeg_pass(
+        &app,
+        transform_args,
+        "conversion-progress",
+        process_state.inner().clone(),
+        media.duration,
+        (40.0, 100.0),
+    )
+    .map_err(|e| {
+        // Cleanup on error
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        format!("Failed to stabilize video: {}", e.message)
+    })?;
+
+    // Clean up temporary directory
+    if temp_dir.exists() {
+        let _ = std::fs::remove_dir_all(&temp_dir);
+    }
+
+    // Emit final progress
+    app.emit("conversion-progress", 100.0).ok();
+
+    Ok(options.output_path)
+}
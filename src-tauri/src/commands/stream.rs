@@ -0,0 +1,164 @@
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::process::Child;
+use tauri::{AppHandle, State, Emitter};
+use crate::commands::settings;
+use crate::utils::ffmpeg;
+use crate::utils::ladder::{self, PlannedRendition, Rendition};
+use crate::utils::probe;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PackageOptions {
+    pub input_path: String,
+    pub output_dir: Option<String>, // Must be empty or missing; default <workspace>/streams/<file name>, cleared before packaging
+    pub formats: Option<Vec<String>>, // "hls" and/or "dash", default both
+    pub segment_type: Option<String>, // HLS segments "fmp4" (default) or "ts"; DASH always uses fMP4
+    pub segment_duration: Option<f64>, // Seconds, default 4
+    pub ladder: Option<Vec<Rendition>>, // Default 1080p/720p/480p/360p at 5000/2800/1400/800 kbit/s
+}
+
+#[derive(Debug, Serialize)]
+pub struct PackageResult {
+    pub output_dir: String,
+    pub hls_master: Option<String>,
+    pub dash_manifest: Option<String>,
+    pub renditions: Vec<PlannedRendition>,
+    pub skipped: Vec<String>, // Rungs above the source resolution
+}
+
+/// Encode an adaptive bitrate ladder and package it as HLS and/or DASH for web players
+#[tauri::command]
+pub async fn package_stream(
+    app: AppHandle,
+    options: PackageOptions,
+    process_state: State<'_, Arc<Mutex<Option<Child>>>>,
+) -> Result<PackageResult, String> {
+    let input_path = PathBuf::from(&options.input_path);
+    if !input_path.exists() {
+        return Err("Input file does not exist".to_string());
+    }
+
+    let formats: Vec<String> = options
+        .formats
+        .clone()
+        .unwrap_or_else(|| vec!["hls".to_string(), "dash".to_string()])
+        .iter()
+        .map(|format| format.to_lowercase())
+        .collect();
+    if formats.is_empty() || formats.iter().any(|format| format != "hls" && format != "dash") {
+        return Err("Formats must be 'hls' and/or 'dash'".to_string());
+    }
+    let want_hls = formats.iter().any(|format| format == "hls");
+    let want_dash = formats.iter().any(|format| format == "dash");
+
+    let segment_type = options.segment_type.as_deref().unwrap_or("fmp4").to_lowercase();
+    if segment_type != "fmp4" && segment_type != "ts" {
+        return Err(format!("Invalid segment type: {}. Use 'fmp4' or 'ts'", segment_type));
+    }
+    let segment_duration = options.segment_duration.unwrap_or(ladder::DEFAULT_SEGMENT_DURATION);
+    if !(1.0..=20.0).contains(&segment_duration) {
+        return Err("Segment duration must be between 1 and 20 seconds".to_string());
+    }
+
+    let media = probe::probe_media(&app, &options.input_path).map_err(|e| e.message)?;
+    let video = media
+        .video
+        .as_ref()
+        .ok_or_else(|| "Input has no video track".to_string())?;
+    let has_audio = media.audio.is_some();
+
+    // Frames are auto-rotated, so the ladder follows the displayed orientation
+    let (width, height) = if video.rotation % 180 == 90 {
+        (video.height, video.width)
+    } else {
+        (video.width, video.height)
+    };
+    let ladder = options.ladder.clone().unwrap_or_else(ladder::default_ladder);
+    let (renditions, skipped) = ladder::plan_renditions(&ladder, width, height)?;
+
+    let output_dir = match &options.output_dir {
+        Some(dir) => {
+            // Segments of an earlier, different ladder would be left behind, but a
+            // folder the user picked is not ours to delete
+            let dir = PathBuf::from(dir);
+            let has_entries = std::fs::read_dir(&dir).is_ok_and(|mut entries| entries.next().is_some());
+            if has_entries {
+                return Err("Output directory is not empty".to_string());
+            }
+            dir
+        }
+        None => {
+            let base_name = input_path
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .unwrap_or("video");
+            let dir = settings::workspace_dir(&app)?.join("streams").join(base_name);
+            // Segments of an earlier, different ladder would be left behind otherwise
+            if dir.exists() {
+                std::fs::remove_dir_all(&dir)
+                    .map_err(|e| format!("Failed to clear output directory: {}", e))?;
+            }
+            dir
+        }
+    };
+    std::fs::create_dir_all(&output_dir)
+        .map_err(|e| format!("Failed to create output directory: {}", e))?;
+
+    // DASH can write HLS playlists for its own fMP4 segments, which saves a second encode
+    let shared_segments = want_hls && want_dash && segment_type == "fmp4";
+    let mut passes: Vec<Vec<String>> = Vec::new();
+    if want_hls && !shared_segments {
+        for rendition in &renditions {
+            std::fs::create_dir_all(output_dir.join(&rendition.name))
+                .map_err(|e| format!("Failed to create output directory: {}", e))?;
+        }
+        let audio_streams = if has_audio { renditions.len() } else { 0 };
+        let mut args = vec!["-i".to_string(), options.input_path.clone()];
+        args.extend(ladder::encode_args(&renditions, video.fps, segment_duration, audio_streams));
+        args.extend(ladder::hls_args(&renditions, has_audio, &segment_type, segment_duration, &output_dir));
+        passes.push(args);
+    }
+    if want_dash {
+        let mut args = vec!["-i".to_string(), options.input_path.clone()];
+        args.extend(ladder::encode_args(&renditions, video.fps, segment_duration, usize::from(has_audio)));
+        args.extend(ladder::dash_args(has_audio, segment_duration, shared_segments, &output_dir));
+        passes.push(args);
+    }
+
+    // Clear any previous process
+    {
+        let mut state = process_state.lock().unwrap();
+        *state = None;
+    }
+
+    let pass_count = passes.len() as f64;
+    for (i, args) in passes.into_iter().enumerate() {
+        let progress_range = (i as f64 * 100.0 / pass_count, (i + 1) as f64 * 100.0 / pass_count);
+        ffmpeg::execute_ffmpeg_pass(
+            &app,
+            args,
+            "conversion-progress",
+            process_state.inner().clone(),
+            media.duration,
+            progress_range,
+        )
+        .map_err(|e| format!("Failed to package stream: {}", e.message))?;
+    }
+
+    let hls_master = output_dir.join("master.m3u8");
+    let dash_manifest = output_dir.join("manifest.mpd");
+    if (want_hls && !hls_master.exists()) || (want_dash && !dash_manifest.exists()) {
+        return Err("FFmpeg did not write the stream manifests".to_string());
+    }
+
+    app.emit("conversion-progress", 100.0).ok();
+
+    Ok(PackageResult {
+        output_dir: output_dir.to_string_lossy().to_string(),
+        hls_master: want_hls.then(|| hls_master.to_string_lossy().to_string()),
+        dash_manifest: want_dash.then(|| dash_manifest.to_string_lossy().to_string()),
+        renditions,
+        skipped,
+    })
+}
@@ -1,15 +1,35 @@
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 use std::process::Child;
 use tauri::{AppHandle, State};
+use crate::commands::settings;
+use crate::utils::audio_filters::{self, AudioOptions};
+use crate::utils::crf_search::{self, CrfSearch, CrfSearchResult, TargetQualityOptions};
 use crate::utils::ffmpeg;
+use crate::utils::filter_graph::FilterGraph;
+use crate::utils::frame_rate;
+use crate::utils::interlace;
+use crate::utils::loudness::{self, LoudnessOptions, LoudnessReport};
+use crate::utils::media_protocol::{self, MediaAccess, MediaFile};
+use crate::utils::media_type::{self, MediaFormat, MediaKind};
+use crate::utils::probe;
+use crate::utils::rotation::{self, RotationOptions};
+use crate::utils::speed;
+use crate::utils::watermark::{self, WatermarkOptions};
+
+/// Extensions offered in the file dialog, and accepted when the content isn't recognised
+const VIDEO_EXTENSIONS: &[&str] = &[
+    "mp4", "avi", "mov", "mkv", "webm", "flv", "wmv", "m4v", "asf", "ogv", "3gp", "ts", "mts", "m2ts", "mpg", "mpeg",
+];
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct VideoInfo {
     pub path: String,
     pub name: String,
     pub size: u64,
+    pub format: Option<MediaFormat>, // Detected from the file content
+    pub warning: Option<String>, // Set when the extension doesn't match the content
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -17,6 +37,25 @@ pub struct ConvertOptions {
     pub input_path: String,
     pub output_path: String,
     pub format: String,
+    pub preset: Option<String>, // Name of a preset from settings
+    pub watermark: Option<WatermarkOptions>, // Overrides the preset's watermark
+    pub speed: Option<f64>, // Playback speed factor (0.25-4.0)
+    pub preserve_pitch: Option<bool>, // Keep audio pitch when changing speed (default true)
+    pub playback: Option<String>, // "forward" (default), "reverse" or "boomerang"
+    pub rotation: Option<RotationOptions>,
+    pub fps: Option<f64>, // Target frame rate
+    pub fps_mode: Option<String>, // "drop" (default), "interpolate" (minterpolate) or "cfr" (normalize variable frame rate)
+    pub deinterlace: Option<String>, // "off" (default), "auto", "yadif" or "bwdif"
+    pub loudness: Option<LoudnessOptions>, // Two-pass EBU R128 loudness normalization
+    pub audio: Option<AudioOptions>, // Gain, fades, channel mixdown, sample rate and bitrate
+    pub target_quality: Option<TargetQualityOptions>, // Search the CRF reaching a VMAF/SSIM score instead of a fixed one
+}
+
+#[derive(Debug, Serialize)]
+pub struct ConvertResult {
+    pub output_path: String,
+    pub loudness: Option<LoudnessReport>, // Before/after measurements when normalizing
+    pub crf_search: Option<CrfSearchResult>, // Chosen CRF and predicted size in target quality mode
 }
 
 /// Open file dialog to select a video file
@@ -29,7 +68,9 @@ pub async fn select_video(app: AppHandle) -> Result<VideoInfo, String> {
 
     app.dialog()
         .file()
-        .add_filter("Video Files", &["mp4", "avi", "mov", "mkv", "webm", "flv", "wmv", "m4v"])
+        .add_filter("Video Files", VIDEO_EXTENSIONS)
+        // Files with a wrong or missing extension are recognised by their content
+        .add_filter("All Files", &["*"])
         .pick_file(move |file_path_opt: Option<FilePath>| {
             let _ = tx.send(file_path_opt);
         });
@@ -41,7 +82,21 @@ pub async fn select_video(app: AppHandle) -> Result<VideoInfo, String> {
     // Convert FilePath to PathBuf
     // FilePath implements Display, so we can convert via string
     let path_str = file_path.to_string();
-    let path_buf = PathBuf::from(&path_str);
+    inspect_video(&PathBuf::from(&path_str))
+}
+
+/// Check a dropped file by its content, so videos with a wrong or missing
+/// extension are accepted and mismatches are reported
+#[tauri::command]
+pub async fn inspect_video_file(file_path: String) -> Result<VideoInfo, String> {
+    let path = PathBuf::from(&file_path);
+    if !path.exists() {
+        return Err("File does not exist".to_string());
+    }
+    inspect_video(&path)
+}
+
+fn inspect_video(path_buf: &Path) -> Result<VideoInfo, String> {
     let path = path_buf.to_string_lossy().to_string();
     let name = path_buf
         .file_name()
@@ -49,13 +104,26 @@ pub async fn select_video(app: AppHandle) -> Result<VideoInfo, String> {
         .unwrap_or("Unknown")
         .to_string();
 
-    let metadata = std::fs::metadata(&path_buf)
+    let metadata = std::fs::metadata(path_buf)
         .map_err(|e| format!("Failed to read file metadata: {}", e))?;
 
+    let format = media_type::detect_file(path_buf);
+    let warning = match format {
+        Some(format) if format.kind() == MediaKind::Image => {
+            return Err(format!("{} is an image, not a video", name));
+        }
+        Some(format) => media_type::extension_warning(path_buf, format),
+        // Formats without a signature (e.g. MPEG-TS) are accepted by extension
+        None if VIDEO_EXTENSIONS.contains(&media_type::extension_of(path_buf).as_str()) => None,
+        None => return Err(format!("{} is not a recognised video file", name)),
+    };
+
     Ok(VideoInfo {
         path,
         name,
         size: metadata.len(),
+        format,
+        warning,
     })
 }
 
@@ -65,7 +133,7 @@ pub async fn convert_video(
     app: AppHandle,
     options: ConvertOptions,
     process_state: State<'_, Arc<Mutex<Option<Child>>>>,
-) -> Result<String, String> {
+) -> Result<ConvertResult, String> {
     let input_path = PathBuf::from(&options.input_path);
     if !input_path.exists() {
         return Err("Input file does not exist".to_string());
@@ -80,11 +148,160 @@ pub async fn convert_video(
     std::fs::create_dir_all(output_dir)
         .map_err(|e| format!("Failed to create output directory: {}", e))?;
 
+    let media = probe::probe_media(&app, &options.input_path).map_err(|e| e.message)?;
+    let source_rotation = media.video.as_ref().map(|v| v.rotation).unwrap_or(0);
+
+    // Rotation metadata only: copy the streams and rewrite the display matrix
+    if let Some(rotation_options) = options.rotation.as_ref().filter(|r| r.is_metadata_mode()) {
+        return rewrite_rotation_metadata(&app, &options, rotation_options, source_rotation, process_state.inner().clone());
+    }
+
+    // Build filters shared by all formats
+    let mut graph = FilterGraph::new();
+    let mut expected_duration = None;
+
+    // Channel mixdown and gain start the audio chain, so loudness is measured and
+    // normalized on the mix that ends up in the output
+    let level_filters = match (options.audio.as_ref(), media.audio.as_ref()) {
+        (Some(audio_options), Some(source)) => audio_filters::level_filters(audio_options, source)?,
+        _ => Vec::new(),
+    };
+    for filter in &level_filters {
+        graph.audio(filter.as_str());
+    }
+
+    // Loudness: measure the source first, then normalize right after the level changes
+    let mut loudness_pass = None;
+    if let Some(loudness_options) = options.loudness.as_ref() {
+        if media.audio.is_none() {
+            return Err("Input has no audio to normalize".to_string());
+        }
+        let target = loudness_options.target()?;
+
+        // Clear any previous process
+        {
+            let mut state = process_state.lock().unwrap();
+            *state = None;
+        }
+
+        let first_pass = loudness::measure_loudness(
+            &app,
+            &options.input_path,
+            &level_filters,
+            &target,
+            process_state.inner().clone(),
+            media.duration,
+            (0.0, 30.0),
+        )
+        .map_err(|e| format!("Failed to measure loudness: {}", e.message))?;
+
+        let sample_rate = media.audio.as_ref().and_then(|a| a.sample_rate).unwrap_or(48000);
+        for filter in loudness::normalization_filters(&target, &first_pass, sample_rate) {
+            graph.audio(filter);
+        }
+        loudness_pass = Some((target, first_pass));
+    }
+
+    // Deinterlace before anything scales or moves the fields
+    let deinterlace = interlace::parse_deinterlace_mode(options.deinterlace.as_deref().unwrap_or("off"))?;
+    if deinterlace != "off" {
+        let analysis = interlace::analyze_interlace(&app, &options.input_path, media.duration)
+            .map_err(|e| e.message)?;
+        interlace::apply_deinterlace(&mut graph, &deinterlace, Some(&analysis))?;
+    }
+
+    rotation::apply_transpose(&mut graph, options.rotation.as_ref())?;
+
+    let playback = options.playback.as_deref().unwrap_or("forward");
+    let speed_factor = options.speed.unwrap_or(1.0);
+    if playback != "forward" || speed_factor != 1.0 {
+        let has_audio = media.audio.is_some();
+        let sample_rate = media.audio.as_ref().and_then(|a| a.sample_rate);
+
+        speed::apply_playback_direction(&mut graph, playback, media.duration, has_audio)?;
+        speed::apply_speed(
+            &mut graph,
+            speed_factor,
+            options.preserve_pitch.unwrap_or(true),
+            sample_rate,
+            has_audio,
+        )?;
+
+        // Progress is measured against the output timeline
+        expected_duration = media.duration.map(|d| {
+            let d = if playback.eq_ignore_ascii_case("boomerang") { d * 2.0 } else { d };
+            d / speed_factor
+        });
+    }
+
+    // Frame rate comes after the speed change so interpolation fills in slow motion
+    frame_rate::apply_frame_rate(
+        &mut graph,
+        options.fps,
+        options.fps_mode.as_deref(),
+        media.video.as_ref().and_then(|v| v.fps),
+        media.audio.is_some(),
+    )?;
+
+    // Fades work on the output timeline, so they use the final duration
+    let mut audio_output_args = Vec::new();
+    if let Some(audio_options) = options.audio.as_ref() {
+        if media.audio.is_some() {
+            let output_duration = expected_duration.or(media.duration);
+            for filter in audio_filters::fade_filters(audio_options, output_duration)? {
+                graph.audio(filter);
+            }
+        }
+        audio_output_args = audio_filters::audio_output_args(audio_options)?;
+    }
+
+    if let Some(watermark) = settings::resolve_watermark(&app, options.watermark.as_ref(), options.preset.as_deref())? {
+        watermark::apply_watermark(&mut graph, &watermark)?;
+    }
+
+    // Target quality: find the CRF on filtered samples of the source, then encode everything with it
+    let mut progress_start = if loudness_pass.is_some() { 30.0 } else { 0.0 };
+    let mut crf_search = None;
+    if let Some(target_options) = options.target_quality.as_ref() {
+        if media.video.is_none() {
+            return Err("Input has no video track".to_string());
+        }
+        let duration = media
+            .duration
+            .ok_or_else(|| "Could not determine the input duration".to_string())?;
+        let encoder = crf_search::encoder_for_format(&options.format);
+        let has_vmaf = ffmpeg::has_filter(&app, "libvmaf").map_err(|e| e.message)?;
+        let settings = target_options.resolve(&encoder, has_vmaf)?;
+
+        let temp_dir = ffmpeg::job_temp_dir("crf");
+
+        let search = CrfSearch {
+            input_path: &options.input_path,
+            graph: graph.video_only(),
+            duration,
+            output_duration: expected_duration.unwrap_or(duration),
+            settings,
+            encoder,
+            work_dir: &temp_dir,
+        };
+
+        // Clear any previous process
+        {
+            let mut state = process_state.lock().unwrap();
+            *state = None;
+        }
+
+        let search_end = progress_start + (100.0 - progress_start) / 2.0;
+        let result = crf_search::search_crf(&app, &search, process_state.inner().clone(), (progress_start, search_end));
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        crf_search = Some(result.map_err(|e| format!("Failed to find a CRF for the target quality: {}", e.message))?);
+        progress_start = search_end;
+    }
+    let crf = crf_search.as_ref().map(|result| result.crf);
+
     // Build FFmpeg command based on format
-    let mut args = vec![
-        "-i".to_string(),
-        options.input_path.clone(),
-    ];
+    let mut args = graph.input_args(&options.input_path);
+    args.extend(graph.output_args());
 
     // Add format-specific encoding options
     match options.format.to_lowercase().as_str() {
@@ -97,7 +314,7 @@ pub async fn convert_video(
                 "-preset".to_string(),
                 "medium".to_string(),
                 "-crf".to_string(),
-                "23".to_string(),
+                crf.unwrap_or(23).to_string(),
                 "-movflags".to_string(),
                 "+faststart".to_string(), // Enable fast start for web playback
                 "-pix_fmt".to_string(),
@@ -137,7 +354,7 @@ pub async fn convert_video(
                 "-c:v".to_string(),
                 "libvpx-vp9".to_string(),
                 "-crf".to_string(),
-                "30".to_string(), // Quality setting (0-63, lower is better)
+                crf.unwrap_or(30).to_string(), // Quality setting (0-63, lower is better)
                 "-b:v".to_string(),
                 "0".to_string(), // Use CRF mode
                 "-c:a".to_string(),
@@ -159,6 +376,14 @@ pub async fn convert_video(
         }
     }
 
+    // The other formats use the encoder's default CRF unless one was searched
+    let format = options.format.to_lowercase();
+    if let (Some(crf), false) = (crf, format == "mp4" || format == "webm") {
+        args.push("-crf".to_string());
+        args.push(crf.to_string());
+    }
+
+    args.extend(audio_output_args);
     args.push("-y".to_string()); // Overwrite output file
     args.push(options.output_path.clone());
 
@@ -169,67 +394,121 @@ pub async fn convert_video(
     }
 
     // Execute FFmpeg with progress tracking
-    ffmpeg::execute_ffmpeg_with_progress(&app, args, "conversion-progress", process_state.inner().clone())
+    let progress_range = (progress_start, 100.0);
+    let log = ffmpeg::execute_ffmpeg_capture(
+        &app,
+        args,
+        "conversion-progress",
+        process_state.inner().clone(),
+        expected_duration,
+        progress_range,
+    )
+    .map_err(|e| e.message)?;
+
+    let loudness = loudness_pass.map(|(target, first_pass)| LoudnessReport {
+        target,
+        before: first_pass.measurement,
+        after: loudness::parse_normalized_loudness(&log),
+    });
+
+    Ok(ConvertResult {
+        output_path: options.output_path,
+        loudness,
+        crf_search,
+    })
+}
+
+/// Change only the rotation/flip metadata of a video without re-encoding
+fn rewrite_rotation_metadata(
+    app: &AppHandle,
+    options: &ConvertOptions,
+    rotation_options: &RotationOptions,
+    source_rotation: u32,
+    process_state: Arc<Mutex<Option<Child>>>,
+) -> Result<ConvertResult, String> {
+    if !rotation::supports_rotation_metadata(&options.format) {
+        return Err("Rotation metadata is only supported for MP4 and MOV output".to_string());
+    }
+    if options.speed.is_some_and(|s| s != 1.0)
+        || options.playback.as_deref().is_some_and(|p| p != "forward")
+        || settings::resolve_watermark(app, options.watermark.as_ref(), options.preset.as_deref())?.is_some()
+        || options.fps.is_some()
+        || options.fps_mode.is_some()
+        || options.deinterlace.as_deref().is_some_and(|d| d != "off")
+        || options.loudness.is_some()
+        || options.audio.is_some()
+        || options.target_quality.is_some()
+    {
+        return Err("Metadata rotation copies the streams and cannot be combined with other video changes".to_string());
+    }
+
+    let mut args = rotation::metadata_input_args(app, source_rotation, rotation_options)?;
+    args.extend(vec![
+        "-i".to_string(),
+        options.input_path.clone(),
+        "-map".to_string(),
+        "0".to_string(),
+        "-c".to_string(),
+        "copy".to_string(),
+        "-y".to_string(),
+        options.output_path.clone(),
+    ]);
+
+    // Clear any previous process
+    {
+        let mut state = process_state.lock().unwrap();
+        *state = None;
+    }
+
+    ffmpeg::execute_ffmpeg_with_progress(app, args, "conversion-progress", process_state)
         .map_err(|e| e.message)?;
 
-    Ok(options.output_path)
+    Ok(ConvertResult {
+        output_path: options.output_path.clone(),
+        loudness: None,
+        crf_search: None,
+    })
 }
 
-/// Read video file and return as base64 data URL for browser playback
-/// This allows playing any video file in the browser
+/// Probe a media file for duration, streams and rotation, optionally analysing interlacing
 #[tauri::command]
-pub async fn read_video_file(file_path: String) -> Result<String, String> {
-    use std::fs;
-    use base64::{Engine, engine::general_purpose};
-    
+pub async fn probe_video(
+    app: AppHandle,
+    file_path: String,
+    analyze_interlace: Option<bool>,
+) -> Result<probe::MediaInfo, String> {
     let path = PathBuf::from(&file_path);
     if !path.exists() {
         return Err("File does not exist".to_string());
     }
 
-    // Check file size - warn if too large (over 100MB)
-    let metadata = fs::metadata(&path)
-        .map_err(|e| format!("Failed to read file metadata: {}", e))?;
-    
-    if metadata.len() > 100 * 1024 * 1024 {
-        return Err("File is too large (>100MB) to load in browser. Please use 'Open in External Player' instead.".to_string());
+    let mut media = probe::probe_media(&app, &file_path).map_err(|e| e.message)?;
+    if analyze_interlace.unwrap_or(false) && media.video.is_some() {
+        media.interlace = Some(
+            interlace::analyze_interlace(&app, &file_path, media.duration).map_err(|e| e.message)?,
+        );
     }
 
-    let file_data = fs::read(&path)
-        .map_err(|e| format!("Failed to read file: {}", e))?;
-
-    // Determine MIME type from file extension
-    let mime_type = if file_path.to_lowercase().ends_with(".mp4") {
-        "video/mp4"
-    } else if file_path.to_lowercase().ends_with(".webm") {
-        "video/webm"
-    } else if file_path.to_lowercase().ends_with(".ogg") || file_path.to_lowercase().ends_with(".ogv") {
-        "video/ogg"
-    } else if file_path.to_lowercase().ends_with(".avi") {
-        "video/x-msvideo"
-    } else if file_path.to_lowercase().ends_with(".mov") {
-        "video/quicktime"
-    } else if file_path.to_lowercase().ends_with(".mkv") {
-        "video/x-matroska"
-    } else {
-        "video/mp4" // Default to mp4
-    };
-
-    // Convert to base64
-    let base64 = general_purpose::STANDARD.encode(&file_data);
-    let data_url = format!("data:{};base64,{}", mime_type, base64);
+    Ok(media)
+}
 
-    Ok(data_url)
+/// Describe a video file for playback through the media protocol, which streams
+/// it in ranges instead of loading the whole file into the webview
+#[tauri::command]
+pub async fn read_video_file(file_path: String, media_access: State<'_, MediaAccess>) -> Result<MediaFile, String> {
+    media_protocol::media_file(&media_access, &PathBuf::from(&file_path))
 }
 
 /// Get a file URL for video playback
-/// Returns the file path for use with convertFileSrc or other methods
+/// Returns the file path for use with `convertFileSrc(path, "ripley")` and allows
+/// the media protocol to serve it
 #[tauri::command]
-pub async fn get_video_url(file_path: String) -> Result<String, String> {
+pub async fn get_video_url(file_path: String, media_access: State<'_, MediaAccess>) -> Result<String, String> {
     let path = PathBuf::from(&file_path);
     if !path.exists() {
         return Err("File does not exist".to_string());
     }
+    media_access.allow(&path);
 
     // Return normalized path
     let normalized = file_path.replace('\\', "/");
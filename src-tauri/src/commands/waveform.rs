@@ -0,0 +1,119 @@
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::process::Child;
+use tauri::ipc::Response;
+use tauri::{AppHandle, State, Emitter};
+use crate::commands::settings;
+use crate::utils::cache::{self, SourceStamp};
+use crate::utils::ffmpeg;
+use crate::utils::peaks::{self, PeakBuilder};
+use crate::utils::probe;
+
+const DEFAULT_BUCKETS: u32 = 2000;
+const MAX_BUCKETS: u32 = 1_000_000;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PeakOptions {
+    pub input_path: String,
+    pub buckets: Option<u32>, // Number of min/max/rms triples across the whole file, default 2000
+}
+
+/// Stored with the cached peaks so they are recomputed when the source changes
+#[derive(Debug, Serialize, Deserialize)]
+struct PeakManifest {
+    source: SourceStamp,
+}
+
+/// Compute waveform peaks for a timeline and return them in the binary format
+/// described on `PeakBuilder::finish`. Results are cached per file in the workspace.
+#[tauri::command]
+pub async fn get_waveform_peaks(
+    app: AppHandle,
+    options: PeakOptions,
+    process_state: State<'_, Arc<Mutex<Option<Child>>>>,
+) -> Result<Response, String> {
+    let input_path = PathBuf::from(&options.input_path);
+    if !input_path.exists() {
+        return Err("Input file does not exist".to_string());
+    }
+
+    let buckets = options.buckets.unwrap_or(DEFAULT_BUCKETS);
+    if buckets == 0 || buckets > MAX_BUCKETS {
+        return Err(format!("Bucket count must be between 1 and {}", MAX_BUCKETS));
+    }
+
+    let workspace = settings::workspace_dir(&app)?;
+    let cache_dir = cache::cache_dir(&workspace, "peaks", &input_path);
+    let cache_file = cache_dir.join(format!("peaks_{}.bin", buckets));
+    let stamp = SourceStamp::read(&input_path)?;
+
+    match cache::read_manifest::<PeakManifest>(&cache_dir) {
+        Some(manifest) if manifest.source == stamp => {
+            if let Ok(data) = std::fs::read(&cache_file) {
+                if peaks::is_valid(&data) {
+                    return Ok(Response::new(data));
+                }
+            }
+        }
+        // Peaks of an older version of the file, at any resolution, are stale
+        _ => {
+            cache::reset_dir(&cache_dir)?;
+            cache::write_manifest(&cache_dir, &PeakManifest { source: stamp })?;
+        }
+    }
+
+    let media = probe::probe_media(&app, &options.input_path).map_err(|e| e.message)?;
+    if media.audio.is_none() {
+        return Err("Input has no audio track".to_string());
+    }
+    let duration = media
+        .duration
+        .ok_or_else(|| "Could not determine the input duration".to_string())?;
+
+    let expected_samples = (duration * peaks::PEAK_SAMPLE_RATE as f64).ceil() as u64;
+    let mut builder = PeakBuilder::new(expected_samples, buckets);
+
+    let args = vec![
+        "-v".to_string(),
+        "error".to_string(),
+        "-i".to_string(),
+        options.input_path.clone(),
+        "-vn".to_string(),
+        "-ac".to_string(),
+        "1".to_string(),
+        "-ar".to_string(),
+        peaks::PEAK_SAMPLE_RATE.to_string(),
+        "-f".to_string(),
+        "f32le".to_string(),
+        "-".to_string(),
+    ];
+
+    // Clear any previous process
+    {
+        let mut state = process_state.lock().unwrap();
+        *state = None;
+    }
+
+    let mut decoded: u64 = 0;
+    let mut last_progress = 0.0;
+    ffmpeg::stream_pcm_f32(&app, args, process_state.inner().clone(), |samples| {
+        builder.push(samples);
+        decoded += samples.len() as u64;
+
+        let progress = (decoded as f64 / expected_samples.max(1) as f64 * 100.0).min(100.0);
+        if progress - last_progress >= 1.0 {
+            app.emit("conversion-progress", progress).ok();
+            last_progress = progress;
+        }
+    })
+    .map_err(|e| format!("Failed to decode audio: {}", e.message))?;
+
+    let data = builder.finish();
+    std::fs::write(&cache_file, &data)
+        .map_err(|e| format!("Failed to cache waveform peaks: {}", e))?;
+
+    app.emit("conversion-progress", 100.0).ok();
+
+    Ok(Response::new(data))
+}
@@ -15,19 +15,43 @@ pub fn run() {
         .plugin(tauri_plugin_fs::init())
         .plugin(tauri_plugin_shell::init())
         .manage(ffmpeg_process.clone())
+        .manage(utils::media_protocol::MediaAccess::default())
+        .manage(commands::playback::PlaybackSessions::default())
+        // Stream workspace media to the webview with Range support
+        .register_asynchronous_uri_scheme_protocol(utils::media_protocol::SCHEME, |ctx, request, responder| {
+            let app = ctx.app_handle().clone();
+            std::thread::spawn(move || {
+                responder.respond(utils::media_protocol::handle_request(&app, &request));
+            });
+        })
         .invoke_handler(tauri::generate_handler![
             commands::video::select_video,
+            commands::video::inspect_video_file,
             commands::video::convert_video,
             commands::video::get_video_url,
             commands::video::read_video_file,
             commands::video::open_file_externally,
             commands::video::cancel_operation,
+            commands::video::probe_video,
             commands::preview::generate_preview,
             commands::preview::read_preview_file,
             commands::settings::load_settings,
             commands::settings::save_settings,
             commands::settings::select_workspace_folder,
             commands::denoise::denoise_video,
+            commands::stabilize::stabilize_video,
+            commands::silence::detect_silence,
+            commands::silence::remove_silence,
+            commands::scene::detect_scenes,
+            commands::scene::generate_scene_thumbnails,
+            commands::scene::generate_scene_chapters,
+            commands::sprites::generate_sprites,
+            commands::waveform::get_waveform_peaks,
+            commands::playback::start_playback,
+            commands::playback::stop_playback,
+            commands::stream::package_stream,
+            commands::quality::compare_quality,
+            commands::comparison::render_comparison,
         ])
         .setup(|app| {
             // Center the main window on startup
@@ -39,6 +63,14 @@ pub fn run() {
             }
             Ok(())
         })
+        .on_window_event(|window, event| {
+            // Don't leave a live transcode running after the player's window is gone
+            if let tauri::WindowEvent::Destroyed = event {
+                let app = window.app_handle();
+                app.state::<commands::playback::PlaybackSessions>()
+                    .stop(&app.state::<utils::media_protocol::MediaAccess>());
+            }
+        })
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
@@ -0,0 +1,82 @@
+use crate::utils::filter_graph::FilterGraph;
+
+pub const DEFAULT_DURATION: f64 = 3.0;
+pub const DEFAULT_FPS: f64 = 12.0;
+pub const DEFAULT_WIDTH: u32 = 480;
+
+/// Validate the frame rate and width shared by GIF and WebP previews
+fn check_size(fps: f64, width: u32) -> Result<(), String> {
+    if !(1.0..=50.0).contains(&fps) {
+        return Err("Animation frame rate must be between 1 and 50 fps".to_string());
+    }
+    if !(16..=1920).contains(&width) {
+        return Err("Animation width must be between 16 and 1920 pixels".to_string());
+    }
+    Ok(())
+}
+
+/// GIF pipeline: build an optimal 256-colour palette from the clip itself, then map
+/// the frames onto it with the requested dithering
+/// ("sierra2_4a" (default), "floyd_steinberg", "bayer" or "none")
+pub fn apply_gif_palette(graph: &mut FilterGraph, fps: f64, width: u32, dither: Option<&str>) -> Result<(), String> {
+    check_size(fps, width)?;
+
+    let dither = match dither.unwrap_or("sierra2_4a").to_lowercase().as_str() {
+        "sierra2_4a" => "dither=sierra2_4a",
+        "floyd_steinberg" => "dither=floyd_steinberg",
+        // Ordered dithering keeps frame-to-frame noise down, which compresses better
+        "bayer" => "dither=bayer:bayer_scale=3",
+        "none" => "dither=none",
+        other => {
+            return Err(format!(
+                "Invalid dither mode: {}. Use 'sierra2_4a', 'floyd_steinberg', 'bayer' or 'none'",
+                other
+            ))
+        }
+    };
+
+    graph.video(format!("fps={}", fps));
+    graph.video(format!("scale={}:-2:flags=lanczos", width));
+    graph.video_graph(format!(
+        "[{{in}}]split[gifpal][gifsrc];[gifpal]palettegen=stats_mode=diff[gifpalette];[gifsrc][gifpalette]paletteuse={}:diff_mode=rectangle[{{out}}]",
+        dither
+    ));
+    Ok(())
+}
+
+/// Animated WebP only needs the frame rate and size adjusted
+pub fn apply_webp_scale(graph: &mut FilterGraph, fps: f64, width: u32) -> Result<(), String> {
+    check_size(fps, width)?;
+    graph.video(format!("fps={}", fps));
+    graph.video(format!("scale={}:-2:flags=lanczos", width));
+    Ok(())
+}
+
+/// Encoder and loop settings. `plays` is how often the animation runs, 0 loops forever.
+pub fn output_args(format: &str, plays: u32) -> Vec<String> {
+    match format {
+        "webp" => vec![
+            "-c:v".to_string(),
+            "libwebp".to_string(),
+            "-quality".to_string(),
+            "75".to_string(),
+            "-an".to_string(),
+            // WebP stores the total number of plays
+            "-loop".to_string(),
+            plays.to_string(),
+        ],
+        _ => {
+            // GIF stores the number of repeats after the first play, -1 plays once
+            let repeats = match plays {
+                0 => 0,
+                1 => -1,
+                n => n as i64 - 1,
+            };
+            vec![
+                "-an".to_string(),
+                "-loop".to_string(),
+                repeats.to_string(),
+            ]
+        }
+    }
+}
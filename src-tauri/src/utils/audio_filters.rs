@@ -0,0 +1,95 @@
+use serde::{Deserialize, Serialize};
+use crate::utils::probe::AudioStreamInfo;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AudioOptions {
+    pub gain_db: Option<f64>, // Volume change in dB (negative is quieter)
+    pub fade_in: Option<f64>, // Fade-in duration in seconds
+    pub fade_out: Option<f64>, // Fade-out duration in seconds, ending at the end of the output
+    pub channels: Option<String>, // "stereo" or "mono" (mixdown), "swap" (left/right), "left" or "right" (keep one channel)
+    pub sample_rate: Option<u32>, // e.g. 44100 or 48000
+    pub bitrate: Option<String>, // e.g. "128k" or "192k"
+}
+
+/// Channel mixdown and gain. These change the level, so they go before loudness
+/// measurement and normalization. `source` is the probed input stream.
+pub fn level_filters(options: &AudioOptions, source: &AudioStreamInfo) -> Result<Vec<String>, String> {
+    let mut filters = Vec::new();
+
+    if let Some(channels) = options.channels.as_deref() {
+        let channels = channels.to_lowercase();
+        // pan fails outright when a mono source has no second channel to read
+        if matches!(channels.as_str(), "swap" | "right") && source.channel_layout.as_deref() == Some("mono") {
+            return Err(format!("Channel mode '{}' needs a stereo source, but the audio is mono", channels));
+        }
+        match channels.as_str() {
+            // aformat lets the resampler apply the standard downmix matrix for any source layout
+            "stereo" => filters.push("aformat=channel_layouts=stereo".to_string()),
+            "mono" => filters.push("aformat=channel_layouts=mono".to_string()),
+            "swap" => filters.push("pan=stereo|c0=c1|c1=c0".to_string()),
+            "left" => filters.push("pan=mono|c0=c0".to_string()),
+            "right" => filters.push("pan=mono|c0=c1".to_string()),
+            other => {
+                return Err(format!(
+                    "Invalid channel mode: {}. Use 'stereo', 'mono', 'swap', 'left' or 'right'",
+                    other
+                ))
+            }
+        }
+    }
+
+    if let Some(gain) = options.gain_db {
+        if !(-60.0..=30.0).contains(&gain) {
+            return Err("Gain must be between -60 and 30 dB".to_string());
+        }
+        if gain != 0.0 {
+            filters.push(format!("volume={}dB", gain));
+        }
+    }
+
+    Ok(filters)
+}
+
+/// Fade-in and fade-out, applied last so they shape the final level.
+/// `duration` is the length of the output and is needed to place the fade-out.
+pub fn fade_filters(options: &AudioOptions, duration: Option<f64>) -> Result<Vec<String>, String> {
+    let mut filters = Vec::new();
+
+    if let Some(fade_in) = options.fade_in.filter(|d| *d > 0.0) {
+        filters.push(format!("afade=t=in:st=0:d={}", fade_in));
+    }
+
+    if let Some(fade_out) = options.fade_out.filter(|d| *d > 0.0) {
+        let duration = duration.ok_or_else(|| "Could not determine duration for the fade-out".to_string())?;
+        if fade_out > duration {
+            return Err("Fade-out is longer than the audio".to_string());
+        }
+        filters.push(format!("afade=t=out:st={:.3}:d={}", duration - fade_out, fade_out));
+    }
+
+    Ok(filters)
+}
+
+/// Output options for sample rate and bitrate (placed after any codec defaults so they take precedence)
+pub fn audio_output_args(options: &AudioOptions) -> Result<Vec<String>, String> {
+    let mut args = Vec::new();
+
+    if let Some(sample_rate) = options.sample_rate {
+        if !(8000..=192000).contains(&sample_rate) {
+            return Err("Sample rate must be between 8000 and 192000 Hz".to_string());
+        }
+        args.push("-ar".to_string());
+        args.push(sample_rate.to_string());
+    }
+
+    if let Some(bitrate) = options.bitrate.as_deref() {
+        let digits = bitrate.trim_end_matches(['k', 'K']);
+        if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+            return Err(format!("Invalid audio bitrate: {}", bitrate));
+        }
+        args.push("-b:a".to_string());
+        args.push(bitrate.to_string());
+    }
+
+    Ok(args)
+}
@@ -0,0 +1,105 @@
+/// Look of a waveform or spectrogram image
+pub struct AudioImageStyle {
+    pub width: u32,
+    pub height: u32, // Per input; comparisons stack two of these
+    pub color: Option<String>, // Waveform colour (e.g. "0x3b82f6") or spectrogram colour scheme (e.g. "intensity", "magma")
+    pub split_channels: bool, // Draw each channel separately
+    pub scale: Option<String>, // "linear" (default) or "log"; amplitude for waveforms, frequency axis for spectrograms
+}
+
+pub const DEFAULT_WIDTH: u32 = 1280;
+pub const DEFAULT_WAVEFORM_HEIGHT: u32 = 240;
+pub const DEFAULT_SPECTROGRAM_HEIGHT: u32 = 512;
+
+/// `showwavespic` filter for the style
+pub fn waveform_filter(style: &AudioImageStyle) -> Result<String, String> {
+    check_size(style)?;
+    let color = style.color.as_deref().unwrap_or("0x3b82f6");
+    if !is_valid_color(color) {
+        return Err(format!("Invalid waveform colour: {}", color));
+    }
+
+    Ok(format!(
+        "showwavespic=s={}x{}:split_channels={}:colors={}:scale={}",
+        style.width,
+        style.height,
+        u8::from(style.split_channels),
+        color,
+        scale_name(style.scale.as_deref())?
+    ))
+}
+
+/// `showspectrumpic` filter for the style (without legend, so stacked images line up)
+pub fn spectrogram_filter(style: &AudioImageStyle) -> Result<String, String> {
+    check_size(style)?;
+    let color = style.color.as_deref().unwrap_or("intensity").to_lowercase();
+    let schemes = [
+        "channel", "intensity", "rainbow", "moreland", "nebulae", "fire", "fiery", "fruit", "cool", "magma",
+        "green", "viridis", "plasma", "cividis", "terrain",
+    ];
+    if !schemes.contains(&color.as_str()) {
+        return Err(format!("Invalid spectrogram colour scheme: {}", color));
+    }
+
+    Ok(format!(
+        "showspectrumpic=s={}x{}:mode={}:color={}:fscale={}:legend=0",
+        style.width,
+        style.height,
+        if style.split_channels { "separate" } else { "combined" },
+        color,
+        scale_name(style.scale.as_deref())?
+    ))
+}
+
+/// FFmpeg arguments rendering one image from the audio of `input_path`, or, with
+/// `compare_path`, both inputs stacked with the original on top
+pub fn image_args(
+    input_path: &str,
+    compare_path: Option<&str>,
+    filter: &str,
+    output_path: &str,
+) -> Vec<String> {
+    let mut args = vec!["-i".to_string(), input_path.to_string()];
+    let graph = match compare_path {
+        Some(compare_path) => {
+            args.push("-i".to_string());
+            args.push(compare_path.to_string());
+            format!("[0:a]{f}[top];[1:a]{f}[bottom];[top][bottom]vstack[img]", f = filter)
+        }
+        None => format!("[0:a]{}[img]", filter),
+    };
+
+    args.extend(vec![
+        "-filter_complex".to_string(),
+        graph,
+        "-map".to_string(),
+        "[img]".to_string(),
+        "-frames:v".to_string(),
+        "1".to_string(),
+        "-c:v".to_string(),
+        "png".to_string(),
+        "-y".to_string(),
+        output_path.to_string(),
+    ]);
+    args
+}
+
+fn check_size(style: &AudioImageStyle) -> Result<(), String> {
+    if !(64..=8192).contains(&style.width) || !(32..=4096).contains(&style.height) {
+        return Err("Image size must be between 64x32 and 8192x4096".to_string());
+    }
+    Ok(())
+}
+
+fn scale_name(scale: Option<&str>) -> Result<&'static str, String> {
+    match scale.unwrap_or("linear").to_lowercase().as_str() {
+        "linear" | "lin" => Ok("lin"),
+        "log" => Ok("log"),
+        other => Err(format!("Invalid scale: {}. Use 'linear' or 'log'", other)),
+    }
+}
+
+/// Colour names or `0xRRGGBB`/`#RRGGBB` values, nothing that could break the filtergraph
+fn is_valid_color(color: &str) -> bool {
+    !color.is_empty() && color.chars().all(|c| c.is_ascii_alphanumeric() || c == '#' || c == '@' || c == '.')
+}
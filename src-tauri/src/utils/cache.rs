@@ -0,0 +1,75 @@
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+const CACHE_FOLDER: &str = ".ripleyflow_cache";
+const MANIFEST_FILE: &str = "manifest.json";
+
+/// Size and modification time of a source file, used to detect stale cache entries
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SourceStamp {
+    pub size: u64,
+    pub modified_ms: u64, // Milliseconds since the Unix epoch
+}
+
+impl SourceStamp {
+    pub fn read(path: &Path) -> Result<Self, String> {
+        let metadata = fs::metadata(path).map_err(|e| format!("Failed to read file metadata: {}", e))?;
+        let modified_ms = metadata
+            .modified()
+            .ok()
+            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+            .map(|elapsed| elapsed.as_millis() as u64)
+            .unwrap_or(0);
+
+        Ok(SourceStamp {
+            size: metadata.len(),
+            modified_ms,
+        })
+    }
+}
+
+/// Cache folder for one source file and kind of derived data (e.g. "sprites"), inside the workspace
+pub fn cache_dir(workspace: &Path, kind: &str, source: &Path) -> PathBuf {
+    let base_name = source
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("video");
+
+    workspace
+        .join(CACHE_FOLDER)
+        .join(kind)
+        .join(format!("{}_{:016x}", base_name, fnv1a_64(source.to_string_lossy().as_bytes())))
+}
+
+/// 64-bit FNV-1a. Unlike `DefaultHasher`, its output is fixed across Rust releases,
+/// so cache folder names survive toolchain updates.
+fn fnv1a_64(bytes: &[u8]) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+    const PRIME: u64 = 0x100000001b3;
+    bytes.iter().fold(OFFSET_BASIS, |hash, byte| (hash ^ *byte as u64).wrapping_mul(PRIME))
+}
+
+/// Read the manifest stored with a cache entry, if there is a valid one
+pub fn read_manifest<T: DeserializeOwned>(dir: &Path) -> Option<T> {
+    let content = fs::read_to_string(dir.join(MANIFEST_FILE)).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+/// Store the manifest after the cache entry has been written completely
+pub fn write_manifest<T: Serialize>(dir: &Path, manifest: &T) -> Result<(), String> {
+    let content = serde_json::to_string_pretty(manifest)
+        .map_err(|e| format!("Failed to serialize cache manifest: {}", e))?;
+    fs::write(dir.join(MANIFEST_FILE), content)
+        .map_err(|e| format!("Failed to write cache manifest: {}", e))
+}
+
+/// Remove a stale entry and create its folder again, empty
+pub fn reset_dir(dir: &Path) -> Result<(), String> {
+    if dir.exists() {
+        fs::remove_dir_all(dir).map_err(|e| format!("Failed to clear cache directory: {}", e))?;
+    }
+    fs::create_dir_all(dir).map_err(|e| format!("Failed to create cache directory: {}", e))
+}
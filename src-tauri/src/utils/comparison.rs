@@ -0,0 +1,174 @@
+use crate::utils::filter_graph;
+use crate::utils::watermark;
+
+pub const DEFAULT_INTERVAL: f64 = 3.0;
+pub const DEFAULT_LABELS: (&str, &str) = ("Before", "After");
+
+/// How the two sources share the frame
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ComparisonLayout {
+    SideBySide, // Both sources next to each other, twice the width
+    Wipe, // Source A left of a divider, source B right of it
+    Alternate, // Full frame, switching between A and B every interval
+}
+
+/// Which source the audio track comes from
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ComparisonAudio {
+    A,
+    B,
+    Alternate, // Switches along with the interval
+    None,
+}
+
+impl ComparisonLayout {
+    pub fn parse(value: Option<&str>) -> Result<Self, String> {
+        match value.map(|v| v.to_lowercase()).as_deref() {
+            None | Some("side_by_side") => Ok(ComparisonLayout::SideBySide),
+            Some("wipe") => Ok(ComparisonLayout::Wipe),
+            Some("alternate") => Ok(ComparisonLayout::Alternate),
+            Some(other) => Err(format!(
+                "Invalid comparison layout: {}. Use 'side_by_side', 'wipe' or 'alternate'",
+                other
+            )),
+        }
+    }
+}
+
+impl ComparisonAudio {
+    pub fn parse(value: Option<&str>) -> Result<Self, String> {
+        match value.map(|v| v.to_lowercase()).as_deref() {
+            None | Some("a") => Ok(ComparisonAudio::A),
+            Some("b") => Ok(ComparisonAudio::B),
+            Some("alternate") => Ok(ComparisonAudio::Alternate),
+            Some("none") => Ok(ComparisonAudio::None),
+            Some(other) => Err(format!(
+                "Invalid comparison audio: {}. Use 'a', 'b', 'alternate' or 'none'",
+                other
+            )),
+        }
+    }
+}
+
+/// One comparison render; input 0 is source A and input 1 source B
+pub struct Comparison<'a> {
+    pub layout: ComparisonLayout,
+    pub width: u32, // Both sources are scaled to this size, usually A's displayed size
+    pub height: u32,
+    pub fps: Option<f64>, // Frame rate of A, B is resampled to it
+    pub labels: Option<(&'a str, &'a str)>,
+    pub split: f64, // Wipe divider position as a fraction of the width
+    pub sweep: bool, // Wipe divider moves across the frame and back instead of standing still
+    pub interval: f64, // Seconds per source when alternating (and per sweep across)
+    pub audio: ComparisonAudio,
+}
+
+/// Filtergraph composing both sources into `[v]`, plus `[a]` when the audio alternates
+pub fn comparison_graph(comparison: &Comparison) -> String {
+    let (width, height) = (comparison.width, comparison.height);
+    let fps_filter = comparison.fps.map(|fps| format!("fps={},", fps)).unwrap_or_default();
+    let prepare = |input: usize| {
+        format!(
+            "[{}:v]{}scale={}:{}:flags=bicubic,setsar=1,format=yuv420p,setpts=PTS-STARTPTS",
+            input, fps_filter, width, height
+        )
+    };
+    let font_size = (height / 24).max(16);
+    let margin = font_size / 2;
+    // A shows while the time within each period is below the interval, B for the rest
+    let period = comparison.interval * 2.0;
+    let showing_a = format!("lt(mod(t\\,{})\\,{})", period, comparison.interval);
+    let showing_b = format!("gte(mod(t\\,{})\\,{})", period, comparison.interval);
+
+    let mut graph = Vec::new();
+    match comparison.layout {
+        ComparisonLayout::SideBySide => {
+            // Each half is labelled before stacking, so every label sits in the top left of its half
+            let (label_a, label_b) = match comparison.labels {
+                Some((a, b)) => (
+                    format!(",{}", label_filter(a, font_size, margin, "left", None)),
+                    format!(",{}", label_filter(b, font_size, margin, "left", None)),
+                ),
+                None => (String::new(), String::new()),
+            };
+            graph.push(format!("{}{}[a_v]", prepare(0), label_a));
+            graph.push(format!("{}{}[b_v]", prepare(1), label_b));
+            graph.push("[a_v][b_v]hstack=inputs=2:shortest=1[v]".to_string());
+        }
+        ComparisonLayout::Wipe => {
+            graph.push(format!("{}[a_v]", prepare(0)));
+            graph.push(format!("{}[b_v]", prepare(1)));
+            let mut composed = if comparison.sweep {
+                // The divider eases across the frame in one interval and back in the next
+                format!(
+                    "[a_v][b_v]blend=all_expr=if(lt(X\\,W*(0.5-0.5*cos(PI*T/{})))\\,A\\,B):shortest=1",
+                    comparison.interval
+                )
+            } else {
+                // Kept even so the crop lines up with the subsampled chroma planes
+                let split_x = ((width as f64 * comparison.split / 2.0).round() as u32 * 2)
+                    .min(width.saturating_sub(2))
+                    .max(2);
+                graph.push(format!("[a_v]crop={}:{}:0:0[a_left]", split_x, height));
+                format!(
+                    "[b_v][a_left]overlay=0:0:shortest=1,drawbox=x={}:y=0:w=2:h={}:color=white@0.8:t=fill",
+                    split_x - 1,
+                    height
+                )
+            };
+            if let Some(labels) = comparison.labels {
+                composed.push_str(&format!(",{}", label_filter(labels.0, font_size, margin, "left", None)));
+                composed.push_str(&format!(",{}", label_filter(labels.1, font_size, margin, "right", None)));
+            }
+            graph.push(format!("{}[v]", composed));
+        }
+        ComparisonLayout::Alternate => {
+            graph.push(format!("{}[a_v]", prepare(0)));
+            graph.push(format!("{}[b_v]", prepare(1)));
+            // overlay passes the main input through while disabled
+            let mut composed = format!("[a_v][b_v]overlay=0:0:shortest=1:enable={}", showing_b);
+            if let Some(labels) = comparison.labels {
+                composed.push_str(&format!(",{}", label_filter(labels.0, font_size, margin, "left", Some(&showing_a))));
+                composed.push_str(&format!(",{}", label_filter(labels.1, font_size, margin, "left", Some(&showing_b))));
+            }
+            graph.push(format!("{}[v]", composed));
+        }
+    }
+
+    if comparison.audio == ComparisonAudio::Alternate {
+        let prepare_audio = |input: usize, enabled: &str, label: &str| {
+            format!(
+                "[{}:a:0]aformat=sample_rates=48000:channel_layouts=stereo,asetpts=PTS-STARTPTS,volume={}:eval=frame[{}]",
+                input, enabled, label
+            )
+        };
+        // Both tracks keep playing, muted while the other source is on
+        graph.push(prepare_audio(0, &showing_a, "a_a"));
+        graph.push(prepare_audio(1, &showing_b, "b_a"));
+        graph.push("[a_a][b_a]amix=inputs=2:duration=shortest:normalize=0[a]".to_string());
+    }
+
+    graph.join(";")
+}
+
+/// drawtext for a source label in the top left or right corner
+fn label_filter(text: &str, font_size: u32, margin: u32, side: &str, enable: Option<&str>) -> String {
+    let x = if side == "right" {
+        format!("w-tw-{}", margin)
+    } else {
+        margin.to_string()
+    };
+    let mut filter = format!(
+        "drawtext={}text={}:fontsize={}:fontcolor=white:box=1:boxcolor=black@0.6:boxborderw={}:x={}:y={}",
+        watermark::default_font_option(),
+        filter_graph::escape_drawtext_text(text),
+        font_size,
+        margin / 2,
+        x,
+        margin
+    );
+    if let Some(enable) = enable {
+        filter.push_str(&format!(":enable={}", enable));
+    }
+    filter
+}
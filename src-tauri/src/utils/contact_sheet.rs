@@ -0,0 +1,179 @@
+use std::path::{Path, PathBuf};
+use crate::utils::filter_graph::{self, FilterGraph};
+use crate::utils::probe::MediaInfo;
+use crate::utils::watermark;
+
+pub const DEFAULT_COLUMNS: u32 = 4;
+pub const DEFAULT_ROWS: u32 = 4;
+pub const DEFAULT_TILE_WIDTH: u32 = 320;
+
+const SPACING: u32 = 4; // Pixels between and around the tiles
+
+pub struct SheetLayout {
+    pub columns: u32,
+    pub rows: u32,
+    pub tile_width: u32,
+    pub timestamps: bool, // Label each tile with its time
+    pub header: Option<String>, // Text line drawn above the grid
+}
+
+/// Evenly spaced times, each in the middle of its slice of the file
+pub fn even_timestamps(count: u32, duration: f64) -> Vec<f64> {
+    (0..count)
+        .map(|i| duration * (i as f64 + 0.5) / count as f64)
+        .collect()
+}
+
+/// One time per scene (its midpoint), thinned out evenly when there are more scenes than tiles
+pub fn scene_timestamps(starts: &[f64], duration: f64, count: u32) -> Vec<f64> {
+    let midpoints: Vec<f64> = starts
+        .iter()
+        .enumerate()
+        .map(|(index, start)| {
+            let end = starts.get(index + 1).copied().unwrap_or(duration);
+            start + (end - start) / 2.0
+        })
+        .collect();
+
+    let count = count as usize;
+    if midpoints.len() <= count {
+        return midpoints;
+    }
+    (0..count)
+        .map(|i| midpoints[i * midpoints.len() / count])
+        .collect()
+}
+
+/// `select` filter passing the first frame at or after each of the (sorted) times
+pub fn frame_select_filter(times: &[f64]) -> String {
+    let terms: Vec<String> = times
+        .iter()
+        .map(|t| format!("gte(t\\,{t:.3})*not(gte(prev_selected_t\\,{t:.3}))", t = t))
+        .collect();
+    format!("select=gt({}\\,0)", terms.join("+"))
+}
+
+/// Scale, label and tile the selected frames into one image, with an optional header
+pub fn apply_sheet_layout(graph: &mut FilterGraph, layout: &SheetLayout) -> Result<(), String> {
+    if layout.columns == 0 || layout.rows == 0 || layout.columns * layout.rows > 100 {
+        return Err("Contact sheet grid must have between 1 and 100 tiles".to_string());
+    }
+    if !(64..=1920).contains(&layout.tile_width) {
+        return Err("Tile width must be between 64 and 1920 pixels".to_string());
+    }
+
+    let font = watermark::default_font_option();
+    graph.video(format!("scale={}:-2", layout.tile_width));
+
+    if layout.timestamps {
+        let font_size = (layout.tile_width / 14).max(12);
+        graph.video(format!(
+            "drawtext={}text={}:fontsize={}:fontcolor=white:box=1:boxcolor=black@0.6:boxborderw=4:x=w-tw-6:y=h-th-6",
+            font,
+            filter_graph::escape_filter_value("%{pts:hms}"),
+            font_size
+        ));
+    }
+
+    graph.video(format!(
+        "tile={}x{}:padding={s}:margin={s}:color=black",
+        layout.columns,
+        layout.rows,
+        s = SPACING
+    ));
+
+    if let Some(header) = layout.header.as_deref() {
+        let sheet_width = layout.columns * (layout.tile_width + SPACING) + SPACING;
+        let font_size = (sheet_width / 60).max(14);
+        let header_height = font_size * 2;
+        graph.video(format!("pad=w=iw:h=ih+{h}:x=0:y={h}:color=black", h = header_height));
+        graph.video(format!(
+            "drawtext={}text={}:fontsize={}:fontcolor=white:x={}:y=({}-th)/2",
+            font,
+            filter_graph::escape_drawtext_text(header),
+            font_size,
+            SPACING * 2,
+            header_height
+        ));
+    }
+
+    Ok(())
+}
+
+/// Header line: file name, duration, resolution and codecs
+pub fn header_text(input_path: &str, media: &MediaInfo) -> String {
+    let mut parts = vec![Path::new(input_path)
+        .file_name()
+        .and_then(|s| s.to_str())
+        .unwrap_or(input_path)
+        .to_string()];
+
+    if let Some(duration) = media.duration {
+        let total = duration.round() as u64;
+        parts.push(format!("{:02}:{:02}:{:02}", total / 3600, (total % 3600) / 60, total % 60));
+    }
+
+    if let Some(video) = &media.video {
+        // Report the size as displayed
+        let (width, height) = if video.rotation % 180 == 90 {
+            (video.height, video.width)
+        } else {
+            (video.width, video.height)
+        };
+        parts.push(format!("{}x{}", width, height));
+    }
+
+    let codecs: Vec<&str> = media
+        .video
+        .as_ref()
+        .map(|v| v.codec.as_str())
+        .into_iter()
+        .chain(media.audio.as_ref().map(|a| a.codec.as_str()))
+        .collect();
+    if !codecs.is_empty() {
+        parts.push(codecs.join(" / "));
+    }
+
+    parts.join("  |  ")
+}
+
+/// Image format from the request, falling back to the output extension
+pub fn image_format(requested: Option<&str>, output_path: &str) -> Result<String, String> {
+    let format = match requested {
+        Some(format) => format.to_lowercase(),
+        None => Path::new(output_path)
+            .extension()
+            .and_then(|s| s.to_str())
+            .unwrap_or("jpg")
+            .to_lowercase(),
+    };
+
+    match format.as_str() {
+        "jpg" | "jpeg" => Ok("jpg".to_string()),
+        "png" => Ok("png".to_string()),
+        "webp" => Ok("webp".to_string()),
+        other => Err(format!("Invalid image format: {}. Use 'jpeg', 'png' or 'webp'", other)),
+    }
+}
+
+/// Output path with the extension matching the image format
+pub fn output_path_for(output_path: &str, format: &str) -> String {
+    PathBuf::from(output_path)
+        .with_extension(format)
+        .to_string_lossy()
+        .to_string()
+}
+
+/// Encoder settings for a single still image
+pub fn image_codec_args(format: &str) -> Vec<String> {
+    match format {
+        "png" => vec!["-c:v".to_string(), "png".to_string()],
+        "webp" => vec![
+            "-c:v".to_string(),
+            "libwebp".to_string(),
+            "-quality".to_string(),
+            "85".to_string(),
+        ],
+        _ => vec!["-c:v".to_string(), "mjpeg".to_string(), "-q:v".to_string(), "3".to_string()],
+    }
+}
@@ -0,0 +1,402 @@
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::process::Child;
+use std::sync::{Arc, Mutex};
+use tauri::AppHandle;
+use crate::utils::ffmpeg::{self, FFmpegError};
+use crate::utils::filter_graph::FilterGraph;
+use crate::utils::highlights;
+use crate::utils::probe;
+use crate::utils::quality::{self, QualityJob, QualityReport};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TargetQualityOptions {
+    pub target: f64, // Score to reach, e.g. 95 for VMAF or 0.98 for SSIM
+    pub metric: Option<String>, // "vmaf" (default when FFmpeg has libvmaf) or "ssim"
+    pub samples: Option<u32>, // Segments encoded per candidate CRF, default 4
+    pub sample_length: Option<f64>, // Seconds per segment, default 4
+    pub min_crf: Option<u32>, // Search range, default 16-38 for H.264 and 15-50 for VP9
+    pub max_crf: Option<u32>,
+}
+
+/// Score of the samples at one CRF
+#[derive(Debug, Clone, Serialize)]
+pub struct CrfTrial {
+    pub crf: u32,
+    pub score: f64,
+    pub sample_bytes: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CrfSearchResult {
+    pub crf: u32,
+    pub metric: String,
+    pub score: f64, // Measured on the samples at the chosen CRF
+    pub met_target: bool, // False when even the lowest CRF falls short; that CRF is used
+    pub predicted_size: u64, // Bytes of the video stream, extrapolated from the samples
+    pub predicted_bitrate: u32, // Video kbit/s
+    pub trials: Vec<CrfTrial>,
+}
+
+/// Encoder options at a given CRF, and the range searched by default
+pub struct CrfEncoder {
+    pub codec_args: fn(u32) -> Vec<String>,
+    pub min_crf: u32,
+    pub max_crf: u32,
+}
+
+const DEFAULT_SAMPLES: u32 = 4;
+const DEFAULT_SAMPLE_LENGTH: f64 = 4.0;
+
+/// Video encoder options of `convert_video` for a format, at the given CRF
+pub fn encoder_for_format(format: &str) -> CrfEncoder {
+    if format.eq_ignore_ascii_case("webm") {
+        CrfEncoder {
+            codec_args: vp9_args,
+            min_crf: 15,
+            max_crf: 50,
+        }
+    } else {
+        CrfEncoder {
+            codec_args: x264_args,
+            min_crf: 16,
+            max_crf: 38,
+        }
+    }
+}
+
+fn x264_args(crf: u32) -> Vec<String> {
+    vec![
+        "-c:v".to_string(),
+        "libx264".to_string(),
+        "-preset".to_string(),
+        "medium".to_string(),
+        "-crf".to_string(),
+        crf.to_string(),
+        "-pix_fmt".to_string(),
+        "yuv420p".to_string(),
+    ]
+}
+
+fn vp9_args(crf: u32) -> Vec<String> {
+    vec![
+        "-c:v".to_string(),
+        "libvpx-vp9".to_string(),
+        "-crf".to_string(),
+        crf.to_string(),
+        "-b:v".to_string(),
+        "0".to_string(),
+        "-pix_fmt".to_string(),
+        "yuv420p".to_string(),
+    ]
+}
+
+/// Target quality options after validation against the encoder
+pub struct TargetSettings {
+    pub vmaf: bool, // Measure VMAF rather than SSIM
+    pub target: f64,
+    pub samples: u32,
+    pub sample_length: f64,
+    pub min_crf: u32,
+    pub max_crf: u32,
+}
+
+impl TargetQualityOptions {
+    /// Check the options against the encoder; `has_vmaf` decides the default metric
+    pub fn resolve(&self, encoder: &CrfEncoder, has_vmaf: bool) -> Result<TargetSettings, String> {
+        let vmaf = match self.metric.as_deref().map(|m| m.to_lowercase()) {
+            None => has_vmaf,
+            Some(metric) if metric == "vmaf" => {
+                if !has_vmaf {
+                    return Err("This FFmpeg build does not include libvmaf".to_string());
+                }
+                true
+            }
+            Some(metric) if metric == "ssim" => false,
+            Some(metric) => return Err(format!("Invalid quality metric: {}. Use 'vmaf' or 'ssim'", metric)),
+        };
+        if vmaf && !(1.0..=100.0).contains(&self.target) {
+            return Err("VMAF target must be between 1 and 100".to_string());
+        }
+        if !vmaf && !(0.5..=1.0).contains(&self.target) {
+            return Err("SSIM target must be between 0.5 and 1".to_string());
+        }
+
+        let samples = self.samples.unwrap_or(DEFAULT_SAMPLES);
+        if !(1..=20).contains(&samples) {
+            return Err("Sample count must be between 1 and 20".to_string());
+        }
+        let sample_length = self.sample_length.unwrap_or(DEFAULT_SAMPLE_LENGTH);
+        if !(1.0..=30.0).contains(&sample_length) {
+            return Err("Sample length must be between 1 and 30 seconds".to_string());
+        }
+
+        let min_crf = self.min_crf.unwrap_or(encoder.min_crf);
+        let max_crf = self.max_crf.unwrap_or(encoder.max_crf);
+        if min_crf > max_crf || max_crf > 63 {
+            return Err("CRF range must be ascending and at most 63".to_string());
+        }
+
+        Ok(TargetSettings {
+            vmaf,
+            target: self.target,
+            samples,
+            sample_length,
+            min_crf,
+            max_crf,
+        })
+    }
+}
+
+impl TargetSettings {
+    /// Most candidates a binary search over the range can try
+    pub fn max_trials(&self) -> u32 {
+        let candidates = self.max_crf - self.min_crf + 1;
+        u32::BITS - candidates.leading_zeros()
+    }
+}
+
+/// One search over a source
+pub struct CrfSearch<'a> {
+    pub input_path: &'a str,
+    pub graph: FilterGraph, // Video filters of the conversion, so samples show the pictures it encodes
+    pub duration: f64, // Source duration
+    pub output_duration: f64, // Duration of the final encode, for the size prediction
+    pub settings: TargetSettings,
+    pub encoder: CrfEncoder,
+    pub work_dir: &'a Path,
+}
+
+/// Binary-search the highest CRF whose samples still score at least the target.
+/// Quality falls as CRF rises, so each trial halves the remaining range.
+pub fn search_crf(
+    app_handle: &AppHandle,
+    search: &CrfSearch,
+    process_state: Arc<Mutex<Option<Child>>>,
+    progress_range: (f64, f64),
+) -> Result<CrfSearchResult, FFmpegError> {
+    std::fs::create_dir_all(search.work_dir).map_err(|e| FFmpegError {
+        message: format!("Failed to create temp directory: {}", e),
+    })?;
+
+    // Short sources are sampled whole
+    let sample_length = search.settings.sample_length.min(search.duration);
+    let samples = if search.duration <= sample_length * search.settings.samples as f64 {
+        1
+    } else {
+        search.settings.samples
+    };
+    let starts = highlights::even_segment_starts(samples, sample_length, search.duration);
+
+    // Filtering the samples takes about as long as one trial
+    let (range_start, range_end) = progress_range;
+    let trial_span = (range_end - range_start) / (search.settings.max_trials() + 1) as f64;
+    let references = filter_samples(
+        app_handle,
+        search,
+        &starts,
+        sample_length,
+        process_state.clone(),
+        (range_start, range_start + trial_span),
+    )?;
+    let range_start = range_start + trial_span;
+
+    let mut trials: Vec<CrfTrial> = Vec::new();
+    let mut best: Option<CrfTrial> = None;
+    let (mut low, mut high) = (search.settings.min_crf as i64, search.settings.max_crf as i64);
+    while low <= high {
+        let crf = ((low + high) / 2) as u32;
+        let trial_start = range_start + trials.len() as f64 * trial_span;
+        let trial = run_trial(
+            app_handle,
+            search,
+            crf,
+            &references,
+            process_state.clone(),
+            (trial_start, trial_start + trial_span),
+        )?;
+
+        if trial.score >= search.settings.target {
+            low = crf as i64 + 1;
+            best = Some(trial.clone());
+        } else {
+            high = crf as i64 - 1;
+        }
+        trials.push(trial);
+    }
+
+    // Not even the best quality in the range reached the target; use it anyway
+    let met_target = best.is_some();
+    let chosen = match best {
+        Some(trial) => trial,
+        None => trials
+            .iter()
+            .find(|trial| trial.crf == search.settings.min_crf)
+            .cloned()
+            .ok_or_else(|| FFmpegError {
+                message: "CRF search did not run any trials".to_string(),
+            })?,
+    };
+
+    // Measured on the output timeline, which speed changes stretch or shrink
+    let sampled_seconds: f64 = references.iter().map(|reference| reference.duration).sum();
+    let bytes_per_second = chosen.sample_bytes as f64 / sampled_seconds;
+    trials.sort_by_key(|trial| trial.crf);
+
+    Ok(CrfSearchResult {
+        crf: chosen.crf,
+        metric: if search.settings.vmaf { "vmaf" } else { "ssim" }.to_string(),
+        score: chosen.score,
+        met_target,
+        predicted_size: (bytes_per_second * search.output_duration) as u64,
+        predicted_bitrate: (bytes_per_second * 8.0 / 1000.0).round() as u32,
+        trials,
+    })
+}
+
+/// Source segment run through the conversion's filters, losslessly encoded
+struct ReferenceSample {
+    path: String,
+    duration: f64, // Seconds after filtering
+    width: u32,
+    height: u32,
+}
+
+/// Cut the samples from the source and apply the conversion's video filters once,
+/// so every trial encodes and is scored against the same filtered pictures
+fn filter_samples(
+    app_handle: &AppHandle,
+    search: &CrfSearch,
+    starts: &[f64],
+    sample_length: f64,
+    process_state: Arc<Mutex<Option<Child>>>,
+    progress_range: (f64, f64),
+) -> Result<Vec<ReferenceSample>, FFmpegError> {
+    let (range_start, range_end) = progress_range;
+    let step = (range_end - range_start) / starts.len() as f64;
+
+    let mut references = Vec::new();
+    for (i, start) in starts.iter().enumerate() {
+        let path = search.work_dir.join(format!("reference_{}.mkv", i)).to_string_lossy().to_string();
+        let mut args = vec![
+            "-ss".to_string(),
+            format!("{:.3}", start),
+            "-t".to_string(),
+            format!("{:.3}", sample_length),
+        ];
+        args.extend(search.graph.input_args(search.input_path));
+        args.extend(search.graph.output_args());
+        args.extend(vec![
+            "-an".to_string(),
+            "-sn".to_string(),
+            "-c:v".to_string(),
+            "ffv1".to_string(),
+            "-y".to_string(),
+            path.clone(),
+        ]);
+        let sample_start = range_start + i as f64 * step;
+        ffmpeg::execute_ffmpeg_pass(
+            app_handle,
+            args,
+            "conversion-progress",
+            process_state.clone(),
+            Some(sample_length),
+            (sample_start, sample_start + step),
+        )?;
+
+        let media = probe::probe_media(app_handle, &path)?;
+        let video = media.video.ok_or_else(|| FFmpegError {
+            message: "Filtered sample has no video".to_string(),
+        })?;
+        references.push(ReferenceSample {
+            path,
+            duration: media.duration.unwrap_or(sample_length),
+            width: video.width,
+            height: video.height,
+        });
+    }
+
+    Ok(references)
+}
+
+/// Encode every sample at one CRF and score it against its filtered reference
+fn run_trial(
+    app_handle: &AppHandle,
+    search: &CrfSearch,
+    crf: u32,
+    references: &[ReferenceSample],
+    process_state: Arc<Mutex<Option<Child>>>,
+    progress_range: (f64, f64),
+) -> Result<CrfTrial, FFmpegError> {
+    let (range_start, range_end) = progress_range;
+    // Each sample is encoded, then decoded twice for the comparison
+    let step = (range_end - range_start) / references.len() as f64;
+
+    let mut sample_bytes = 0;
+    let mut weighted_score = 0.0;
+    let mut frames = 0;
+    for (i, reference) in references.iter().enumerate() {
+        let sample_path = search.work_dir.join(format!("sample_{}_crf{}.mkv", i, crf));
+        let sample_start = range_start + i as f64 * step;
+
+        let mut args = vec![
+            "-i".to_string(),
+            reference.path.clone(),
+            "-map".to_string(),
+            "0:v:0".to_string(),
+        ];
+        args.extend((search.encoder.codec_args)(crf));
+        args.push("-y".to_string());
+        args.push(sample_path.to_string_lossy().to_string());
+        ffmpeg::execute_ffmpeg_pass(
+            app_handle,
+            args,
+            "conversion-progress",
+            process_state.clone(),
+            Some(reference.duration),
+            (sample_start, sample_start + step / 2.0),
+        )?;
+
+        sample_bytes += std::fs::metadata(&sample_path).map(|m| m.len()).unwrap_or(0);
+
+        let sample = sample_path.to_string_lossy().to_string();
+        let metrics_dir = search.work_dir.join(format!("metrics_{}_crf{}", i, crf));
+        let job = QualityJob {
+            reference: &reference.path,
+            reference_options: Vec::new(),
+            distorted: &sample,
+            distorted_options: Vec::new(),
+            width: reference.width,
+            height: reference.height,
+            fps: None,
+            vmaf: search.settings.vmaf,
+            work_dir: &metrics_dir,
+        };
+        let report = quality::measure_quality(
+            app_handle,
+            &job,
+            process_state.clone(),
+            Some(reference.duration),
+            (sample_start + step / 2.0, sample_start + step),
+        )?;
+        let _ = std::fs::remove_file(&sample_path);
+
+        let (score, count) = sample_score(&report, search.settings.vmaf);
+        weighted_score += score * count as f64;
+        frames += count;
+    }
+
+    Ok(CrfTrial {
+        crf,
+        score: if frames > 0 { weighted_score / frames as f64 } else { 0.0 },
+        sample_bytes,
+    })
+}
+
+fn sample_score(report: &QualityReport, vmaf: bool) -> (f64, usize) {
+    let series = match (&report.vmaf, vmaf) {
+        (Some(vmaf), true) => vmaf,
+        _ => &report.ssim,
+    };
+    (series.mean, series.values.len())
+}
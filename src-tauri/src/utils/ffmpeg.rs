@@ -1,6 +1,7 @@
 use std::path::PathBuf;
 use std::process::{Command, Stdio, Child};
-use std::io::{BufRead, BufReader};
+use std::io::{BufRead, BufReader, Read};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
 use tauri::{AppHandle, Emitter, Manager};
 
@@ -69,6 +70,48 @@ pub fn execute_ffmpeg_with_progress(
     event_name: &str,
     process_state: Arc<Mutex<Option<Child>>>,
 ) -> Result<String, FFmpegError> {
+    execute_ffmpeg_with_expected_duration(app_handle, args, event_name, process_state, None)
+}
+
+/// Execute FFmpeg command and parse progress against the expected output duration
+/// instead of the input duration FFmpeg reports (for filters that change the timeline)
+pub fn execute_ffmpeg_with_expected_duration(
+    app_handle: &AppHandle,
+    args: Vec<String>,
+    event_name: &str,
+    process_state: Arc<Mutex<Option<Child>>>,
+    expected_duration: Option<f64>,
+) -> Result<String, FFmpegError> {
+    execute_ffmpeg_pass(app_handle, args, event_name, process_state, expected_duration, (0.0, 100.0))
+}
+
+/// Execute one FFmpeg pass of a multi-pass job, mapping its progress into
+/// `progress_range` (start and end percentage of the whole job)
+pub fn execute_ffmpeg_pass(
+    app_handle: &AppHandle,
+    args: Vec<String>,
+    event_name: &str,
+    process_state: Arc<Mutex<Option<Child>>>,
+    expected_duration: Option<f64>,
+    progress_range: (f64, f64),
+) -> Result<String, FFmpegError> {
+    execute_ffmpeg_capture(app_handle, args, event_name, process_state, expected_duration, progress_range)?;
+    Ok("Conversion completed successfully".to_string())
+}
+
+/// Execute one FFmpeg pass like `execute_ffmpeg_pass` and return its log output
+/// (without progress lines), for filters that print their results, e.g. `loudnorm`
+pub fn execute_ffmpeg_capture(
+    app_handle: &AppHandle,
+    args: Vec<String>,
+    event_name: &str,
+    process_state: Arc<Mutex<Option<Child>>>,
+    expected_duration: Option<f64>,
+    progress_range: (f64, f64),
+) -> Result<String, FFmpegError> {
+    let (range_start, range_end) = progress_range;
+    let scale_progress = |progress: f64| range_start + progress * (range_end - range_start) / 100.0;
+
     let ffmpeg_path = find_ffmpeg_binary(app_handle)?;
 
     let mut cmd = Command::new(&ffmpeg_path);
@@ -97,12 +140,13 @@ pub fn execute_ffmpeg_with_progress(
     }
 
     let reader = BufReader::new(stderr);
-    let mut duration: Option<f64> = None;
+    let mut duration: Option<f64> = expected_duration;
     let mut last_progress = 0.0;
     let mut last_emit_time = std::time::Instant::now();
+    let mut log = String::new();
 
     // Emit initial progress to show activity
-    app_handle.emit(event_name, 1.0).ok();
+    app_handle.emit(event_name, scale_progress(1.0)).ok();
 
     for line in reader.lines() {
         // Check if process was cancelled
@@ -115,26 +159,27 @@ pub fn execute_ffmpeg_with_progress(
             }
         }
 
-        let line_result = line;
-        if let Ok(line) = line_result {
+        let Ok(raw_line) = line else { continue };
+        // Stats lines end with '\r', so filter output can follow on the same line
+        for line in raw_line.split('\r') {
             // Parse duration
             if duration.is_none() {
-                if let Some(dur) = parse_duration(&line) {
+                if let Some(dur) = parse_duration(line) {
                     duration = Some(dur);
                     // Emit a small progress when duration is found
-                    app_handle.emit(event_name, 2.0).ok();
+                    app_handle.emit(event_name, scale_progress(2.0)).ok();
                 }
             }
 
             // Parse current time and calculate progress
-            if let Some(current_time) = parse_time(&line) {
+            if let Some(current_time) = parse_time(line) {
                 if let Some(dur) = duration {
                     if dur > 0.0 {
                         let progress = (current_time / dur * 100.0).min(100.0);
                         // Emit progress more frequently (every 0.5% or every 200ms)
                         let time_since_last_emit = last_emit_time.elapsed();
                         if (progress - last_progress).abs() > 0.5 || time_since_last_emit.as_millis() > 200 {
-                            app_handle.emit(event_name, progress).ok();
+                            app_handle.emit(event_name, scale_progress(progress)).ok();
                             last_progress = progress;
                             last_emit_time = std::time::Instant::now();
                         }
@@ -143,12 +188,236 @@ pub fn execute_ffmpeg_with_progress(
                     // If we have current_time but no duration yet, emit a small progress
                     // to show that processing has started
                     if last_progress < 5.0 {
-                        app_handle.emit(event_name, 3.0).ok();
+                        app_handle.emit(event_name, scale_progress(3.0)).ok();
                         last_progress = 3.0;
                     }
                 }
+            } else {
+                log.push_str(line);
+                log.push('\n');
+            }
+        }
+    }
+
+    // Clear the process state
+    let mut state = process_state.lock().unwrap();
+    let mut child = state.take().ok_or_else(|| FFmpegError {
+        message: "Process handle not found".to_string(),
+    })?;
+
+    let output = child.wait().map_err(|e| FFmpegError {
+        message: format!("Failed to wait for FFmpeg process: {}", e),
+    })?;
+
+    if output.success() {
+        Ok(log)
+    } else {
+        Err(FFmpegError {
+            message: format!("FFmpeg process exited with code: {:?}", output.code()),
+        })
+    }
+}
+
+/// Browser-compatible H.264/AAC output settings
+pub fn h264_aac_args() -> Vec<String> {
+    vec![
+        "-c:v".to_string(),
+        "libx264".to_string(),
+        "-preset".to_string(),
+        "medium".to_string(),
+        "-crf".to_string(),
+        "23".to_string(),
+        "-pix_fmt".to_string(),
+        "yuv420p".to_string(),
+        "-c:a".to_string(),
+        "aac".to_string(),
+        "-movflags".to_string(),
+        "+faststart".to_string(),
+    ]
+}
+
+static JOB_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Temporary directory for one job (not created). Unique per process and call, so
+/// jobs on files with the same name (e.g. `clip.mp4` from two folders) don't share it.
+pub fn job_temp_dir(job: &str) -> PathBuf {
+    let id = JOB_COUNTER.fetch_add(1, Ordering::Relaxed);
+    std::env::temp_dir().join(format!("ripleyflow_{}_{}_{}", job, std::process::id(), id))
+}
+
+/// Run a short FFmpeg command and return its stderr output, regardless of the exit code
+/// (used for probing, where FFmpeg exits with an error because no output is given)
+pub fn read_ffmpeg_output(app_handle: &AppHandle, args: Vec<String>) -> Result<String, FFmpegError> {
+    let ffmpeg_path = find_ffmpeg_binary(app_handle)?;
+
+    let mut cmd = Command::new(&ffmpeg_path);
+    cmd.args(&args);
+    cmd.stdin(Stdio::null());
+
+    // Hide console window on Windows (CREATE_NO_WINDOW = 0x08000000)
+    #[cfg(target_os = "windows")]
+    cmd.creation_flags(0x08000000);
+
+    let output = cmd
+        .output()
+        .map_err(|e| FFmpegError {
+            message: format!("Failed to run FFmpeg: {}", e),
+        })?;
+
+    Ok(String::from_utf8_lossy(&output.stderr).to_string())
+}
+
+/// Start a long-running FFmpeg process in the background (output discarded) and
+/// return its handle, e.g. for live transcoding that the caller stops itself
+pub fn spawn_ffmpeg(app_handle: &AppHandle, args: Vec<String>) -> Result<Child, FFmpegError> {
+    let ffmpeg_path = find_ffmpeg_binary(app_handle)?;
+
+    let mut cmd = Command::new(&ffmpeg_path);
+    cmd.args(&args);
+    cmd.stdin(Stdio::null());
+    cmd.stdout(Stdio::null());
+    cmd.stderr(Stdio::null());
+
+    // Hide console window on Windows (CREATE_NO_WINDOW = 0x08000000)
+    #[cfg(target_os = "windows")]
+    cmd.creation_flags(0x08000000);
+
+    cmd.spawn().map_err(|e| FFmpegError {
+        message: format!("Failed to spawn FFmpeg process: {}", e),
+    })
+}
+
+/// Check whether the FFmpeg build includes a filter (e.g. `vidstabdetect` or `libvmaf`)
+pub fn has_filter(app_handle: &AppHandle, filter_name: &str) -> Result<bool, FFmpegError> {
+    let ffmpeg_path = find_ffmpeg_binary(app_handle)?;
+
+    let mut cmd = Command::new(&ffmpeg_path);
+    cmd.args(["-hide_banner", "-filters"]);
+    cmd.stdin(Stdio::null());
+
+    // Hide console window on Windows (CREATE_NO_WINDOW = 0x08000000)
+    #[cfg(target_os = "windows")]
+    cmd.creation_flags(0x08000000);
+
+    let output = cmd
+        .output()
+        .map_err(|e| FFmpegError {
+            message: format!("Failed to run FFmpeg: {}", e),
+        })?;
+
+    // Lines look like " T.. vidstabdetect     V->V       Extract relative transformations..."
+    let filters = String::from_utf8_lossy(&output.stdout);
+    Ok(filters
+        .lines()
+        .any(|line| line.split_whitespace().nth(1) == Some(filter_name)))
+}
+
+/// Major version of the FFmpeg build, or None for builds without a release number
+/// (git snapshots report e.g. "N-113456-g1234abcd")
+pub fn major_version(app_handle: &AppHandle) -> Result<Option<u32>, FFmpegError> {
+    let ffmpeg_path = find_ffmpeg_binary(app_handle)?;
+
+    let mut cmd = Command::new(&ffmpeg_path);
+    cmd.args(["-hide_banner", "-version"]);
+    cmd.stdin(Stdio::null());
+
+    // Hide console window on Windows (CREATE_NO_WINDOW = 0x08000000)
+    #[cfg(target_os = "windows")]
+    cmd.creation_flags(0x08000000);
+
+    let output = cmd
+        .output()
+        .map_err(|e| FFmpegError {
+            message: format!("Failed to run FFmpeg: {}", e),
+        })?;
+
+    // First line looks like "ffmpeg version 6.1.1-3ubuntu5 Copyright ..." or "ffmpeg version n7.0 ..."
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    Ok(stdout
+        .split_whitespace()
+        .skip_while(|word| *word != "version")
+        .nth(1)
+        .map(|version| version.trim_start_matches('n'))
+        .and_then(|version| version.split(['.', '-']).next())
+        .and_then(|major| major.parse::<u32>().ok()))
+}
+
+/// Run FFmpeg writing raw little-endian f32 samples (`-f f32le -`) to stdout and
+/// hand them to `on_samples` in chunks as they are decoded
+pub fn stream_pcm_f32(
+    app_handle: &AppHandle,
+    args: Vec<String>,
+    process_state: Arc<Mutex<Option<Child>>>,
+    mut on_samples: impl FnMut(&[f32]),
+) -> Result<(), FFmpegError> {
+    let ffmpeg_path = find_ffmpeg_binary(app_handle)?;
+
+    let mut cmd = Command::new(&ffmpeg_path);
+    cmd.args(&args);
+    cmd.stdin(Stdio::null());
+    cmd.stdout(Stdio::piped());
+    cmd.stderr(Stdio::null());
+
+    // Hide console window on Windows (CREATE_NO_WINDOW = 0x08000000)
+    #[cfg(target_os = "windows")]
+    cmd.creation_flags(0x08000000);
+
+    let mut child = cmd
+        .spawn()
+        .map_err(|e| FFmpegError {
+            message: format!("Failed to spawn FFmpeg process: {}", e),
+        })?;
+
+    let mut stdout = child.stdout.take().ok_or_else(|| FFmpegError {
+        message: "Failed to capture stdout".to_string(),
+    })?;
+
+    // Store the process handle for cancellation
+    {
+        let mut state = process_state.lock().unwrap();
+        *state = Some(child);
+    }
+
+    let mut buffer = vec![0u8; 64 * 1024];
+    let mut partial: Vec<u8> = Vec::with_capacity(4); // Bytes of a sample split across reads
+    let mut samples: Vec<f32> = Vec::with_capacity(buffer.len() / 4 + 1);
+
+    loop {
+        // Check if process was cancelled
+        {
+            let state = process_state.lock().unwrap();
+            if state.is_none() {
+                return Err(FFmpegError {
+                    message: "Operation cancelled by user".to_string(),
+                });
             }
         }
+
+        let read = stdout.read(&mut buffer).map_err(|e| FFmpegError {
+            message: format!("Failed to read FFmpeg output: {}", e),
+        })?;
+        if read == 0 {
+            break;
+        }
+
+        samples.clear();
+        let mut bytes = &buffer[..read];
+        if !partial.is_empty() {
+            let needed = (4 - partial.len()).min(bytes.len());
+            partial.extend_from_slice(&bytes[..needed]);
+            bytes = &bytes[needed..];
+            if partial.len() == 4 {
+                samples.push(f32::from_le_bytes([partial[0], partial[1], partial[2], partial[3]]));
+                partial.clear();
+            }
+        }
+
+        let chunks = bytes.chunks_exact(4);
+        let remainder = chunks.remainder();
+        samples.extend(chunks.map(|c| f32::from_le_bytes([c[0], c[1], c[2], c[3]])));
+        partial.extend_from_slice(remainder);
+
+        on_samples(&samples);
     }
 
     // Clear the process state
@@ -162,7 +431,7 @@ pub fn execute_ffmpeg_with_progress(
     })?;
 
     if output.success() {
-        Ok("Conversion completed successfully".to_string())
+        Ok(())
     } else {
         Err(FFmpegError {
             message: format!("FFmpeg process exited with code: {:?}", output.code()),
@@ -197,7 +466,7 @@ pub fn cancel_ffmpeg_operation(process_state: Arc<Mutex<Option<Child>>>) -> Resu
 
 
 /// Parse duration from FFmpeg output line
-fn parse_duration(line: &str) -> Option<f64> {
+pub fn parse_duration(line: &str) -> Option<f64> {
     if let Some(start) = line.find("Duration: ") {
         let duration_str = &line[start + 10..];
         if let Some(end) = duration_str.find(',') {
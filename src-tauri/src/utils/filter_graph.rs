@@ -0,0 +1,239 @@
+/// Step in a video or audio chain
+#[derive(Debug, Clone)]
+enum Step {
+    /// Plain filter applied to the current stream, e.g. `scale=1280:-2`
+    Filter(String),
+    /// Graph fragment using `{in}` and `{out}` as the input/output link labels
+    Graph(String),
+}
+
+/// Collects the filters for a single FFmpeg run and renders them either as
+/// simple `-vf`/`-af` chains or, when extra inputs are involved, as a
+/// `-filter_complex` graph with explicit `-map` arguments.
+#[derive(Debug, Clone, Default)]
+pub struct FilterGraph {
+    input_options: Vec<String>,
+    inputs: Vec<(Vec<String>, String)>, // Options placed before `-i`, and the path
+    output_options: Vec<String>,
+    sources: Vec<String>,
+    video: Vec<Step>,
+    audio: Vec<Step>,
+}
+
+impl FilterGraph {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add an option for the main input (placed before its `-i`)
+    pub fn add_input_option(&mut self, option: impl Into<String>) {
+        self.input_options.push(option.into());
+    }
+
+    /// Add an output option that belongs with the filters (e.g. `-fps_mode cfr`)
+    pub fn add_output_option(&mut self, option: impl Into<String>) {
+        self.output_options.push(option.into());
+    }
+
+    /// Register an additional input file and return its FFmpeg input index
+    /// (the main input is always index 0)
+    pub fn add_input(&mut self, path: &str) -> usize {
+        self.add_input_with_options(path, Vec::new())
+    }
+
+    /// Register an additional input with its own input options, e.g. `-ss`/`-t` to read one segment
+    pub fn add_input_with_options(&mut self, path: &str, options: Vec<String>) -> usize {
+        self.inputs.push((options, path.to_string()));
+        self.inputs.len()
+    }
+
+    /// Add a standalone graph fragment, e.g. preparing an overlay input
+    pub fn add_source(&mut self, fragment: impl Into<String>) {
+        self.sources.push(fragment.into());
+    }
+
+    /// Append a filter to the main video chain
+    pub fn video(&mut self, filter: impl Into<String>) {
+        self.video.push(Step::Filter(filter.into()));
+    }
+
+    /// Append a filter to the main audio chain
+    pub fn audio(&mut self, filter: impl Into<String>) {
+        self.audio.push(Step::Filter(filter.into()));
+    }
+
+    /// Append a graph fragment to the video chain (`{in}`/`{out}` are replaced with link labels)
+    pub fn video_graph(&mut self, fragment: impl Into<String>) {
+        self.video.push(Step::Graph(fragment.into()));
+    }
+
+    /// Append a graph fragment to the audio chain (`{in}`/`{out}` are replaced with link labels)
+    pub fn audio_graph(&mut self, fragment: impl Into<String>) {
+        self.audio.push(Step::Graph(fragment.into()));
+    }
+
+    /// Copy without the audio chain, for video-only runs over the same filters
+    pub fn video_only(&self) -> FilterGraph {
+        FilterGraph {
+            audio: Vec::new(),
+            ..self.clone()
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inputs.is_empty() && self.sources.is_empty() && self.video.is_empty() && self.audio.is_empty()
+    }
+
+    /// `-i` arguments for the main input followed by the extra inputs
+    pub fn input_args(&self, main_input: &str) -> Vec<String> {
+        let mut args = self.input_options.clone();
+        args.push("-i".to_string());
+        args.push(main_input.to_string());
+        for (options, input) in &self.inputs {
+            args.extend(options.iter().cloned());
+            args.push("-i".to_string());
+            args.push(input.clone());
+        }
+        args
+    }
+
+    /// Filter and mapping arguments, to be placed before the output path
+    pub fn output_args(&self) -> Vec<String> {
+        let mut args = self.output_options.clone();
+        if self.is_empty() {
+            return args;
+        }
+
+        let is_simple = self.inputs.is_empty()
+            && self.sources.is_empty()
+            && self.video.iter().chain(self.audio.iter()).all(|step| matches!(step, Step::Filter(_)));
+
+        if is_simple {
+            if !self.video.is_empty() {
+                args.push("-vf".to_string());
+                args.push(join_filters(&self.video));
+            }
+            if !self.audio.is_empty() {
+                args.push("-af".to_string());
+                args.push(join_filters(&self.audio));
+            }
+            return args;
+        }
+
+        let mut fragments = self.sources.clone();
+        let video_label = render_chain(&self.video, "0:v", "v", &mut fragments);
+        let audio_label = render_chain(&self.audio, "0:a", "a", &mut fragments);
+
+        args.push("-filter_complex".to_string());
+        args.push(fragments.join(";"));
+        args.push("-map".to_string());
+        args.push(video_label.map(|l| format!("[{}]", l)).unwrap_or_else(|| "0:v?".to_string()));
+        args.push("-map".to_string());
+        args.push(audio_label.map(|l| format!("[{}]", l)).unwrap_or_else(|| "0:a?".to_string()));
+        args
+    }
+}
+
+fn join_filters(steps: &[Step]) -> String {
+    steps
+        .iter()
+        .map(|step| match step {
+            Step::Filter(f) | Step::Graph(f) => f.as_str(),
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Render a chain into graph fragments and return the final output label
+fn render_chain(steps: &[Step], input: &str, prefix: &str, fragments: &mut Vec<String>) -> Option<String> {
+    if steps.is_empty() {
+        return None;
+    }
+
+    let mut current = input.to_string();
+    let mut pending: Vec<String> = Vec::new();
+    let mut counter = 0;
+
+    let next_label = |counter: &mut usize| {
+        *counter += 1;
+        format!("{}{}", prefix, counter)
+    };
+
+    for step in steps {
+        match step {
+            Step::Filter(filter) => pending.push(filter.clone()),
+            Step::Graph(fragment) => {
+                if !pending.is_empty() {
+                    let out = next_label(&mut counter);
+                    fragments.push(format!("[{}]{}[{}]", current, pending.join(","), out));
+                    pending.clear();
+                    current = out;
+                }
+                let out = next_label(&mut counter);
+                fragments.push(fragment.replace("{in}", &current).replace("{out}", &out));
+                current = out;
+            }
+        }
+    }
+
+    if !pending.is_empty() {
+        let out = next_label(&mut counter);
+        fragments.push(format!("[{}]{}[{}]", current, pending.join(","), out));
+        current = out;
+    }
+
+    Some(current)
+}
+
+/// Escape a value for use as a filter option (first escaping level)
+pub fn escape_option_value(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, '\\' | '\'' | ':') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Escape a filter option value so it survives the filtergraph parser (both escaping levels)
+pub fn escape_filter_value(value: &str) -> String {
+    let option_value = escape_option_value(value);
+    let mut escaped = String::with_capacity(option_value.len());
+    for c in option_value.chars() {
+        if matches!(c, '\\' | '\'' | '[' | ']' | ',' | ';') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Escape literal text for drawtext's `text` option. drawtext expands %{...} sequences,
+/// so backslashes and percent signs are escaped for it before the filtergraph levels.
+pub fn escape_drawtext_text(text: &str) -> String {
+    escape_filter_value(&text.replace('\\', "\\\\").replace('%', "\\%"))
+}
+
+/// Escape a file path for use inside a filter option (FFmpeg accepts forward slashes on Windows)
+pub fn escape_filter_path(path: &str) -> String {
+    escape_filter_value(&path.replace('\\', "/"))
+}
+
+/// Build an `enable` expression that is true inside any of the given ranges
+pub fn enable_expression(ranges: &[(f64, Option<f64>)]) -> Option<String> {
+    if ranges.is_empty() {
+        return None;
+    }
+
+    let terms: Vec<String> = ranges
+        .iter()
+        .map(|(start, end)| match end {
+            Some(end) => format!("between(t\\,{}\\,{})", start, end),
+            None => format!("gte(t\\,{})", start),
+        })
+        .collect();
+
+    Some(terms.join("+"))
+}
@@ -0,0 +1,54 @@
+use crate::utils::filter_graph::FilterGraph;
+
+const MAX_FPS: f64 = 240.0;
+
+/// Add a frame rate change to the graph.
+///
+/// - `drop`: the `fps` filter drops or duplicates frames (default)
+/// - `interpolate`: `minterpolate` synthesises motion-compensated frames, which
+///   also smooths slow motion when combined with a speed change
+/// - `cfr`: normalises variable frame rate footage to a constant rate and keeps
+///   the audio in sync by resampling it against the timestamps
+pub fn apply_frame_rate(
+    graph: &mut FilterGraph,
+    fps: Option<f64>,
+    mode: Option<&str>,
+    source_fps: Option<f64>,
+    has_audio: bool,
+) -> Result<(), String> {
+    let mode = mode.unwrap_or("drop").to_lowercase();
+    if fps.is_none() && mode == "drop" {
+        return Ok(());
+    }
+
+    let target = fps
+        .or(source_fps)
+        .ok_or_else(|| "Could not determine the source frame rate, please set a target fps".to_string())?;
+    if target <= 0.0 || target > MAX_FPS {
+        return Err(format!("Frame rate must be between 0 and {} fps", MAX_FPS));
+    }
+
+    match mode.as_str() {
+        "drop" => graph.video(format!("fps={}", target)),
+        "interpolate" => graph.video(format!(
+            "minterpolate=fps={}:mi_mode=mci:mc_mode=aobmc:me_mode=bidir:vsbmc=1",
+            target
+        )),
+        "cfr" => {
+            graph.video(format!("fps={}", target));
+            graph.add_output_option("-fps_mode");
+            graph.add_output_option("cfr");
+            if has_audio {
+                graph.audio("aresample=async=1:first_pts=0");
+            }
+        }
+        other => {
+            return Err(format!(
+                "Invalid frame rate mode: {}. Use 'drop', 'interpolate' or 'cfr'",
+                other
+            ))
+        }
+    }
+
+    Ok(())
+}
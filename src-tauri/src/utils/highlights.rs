@@ -0,0 +1,129 @@
+use crate::utils::filter_graph::FilterGraph;
+
+pub const DEFAULT_SEGMENTS: u32 = 5;
+pub const DEFAULT_SEGMENT_LENGTH: f64 = 2.0;
+pub const DEFAULT_CROSSFADE: f64 = 0.5;
+
+/// Segments to cut from the source and how they blend into each other
+pub struct HighlightPlan {
+    pub starts: Vec<f64>,
+    pub length: f64, // Seconds per segment
+    pub crossfade: f64, // Seconds of overlap between neighbouring segments
+}
+
+impl HighlightPlan {
+    pub fn new(starts: Vec<f64>, length: f64, crossfade: f64) -> Result<Self, String> {
+        if starts.len() < 2 || starts.len() > 20 {
+            return Err("Highlights need between 2 and 20 segments".to_string());
+        }
+        if !(0.5..=30.0).contains(&length) {
+            return Err("Highlight segments must be between 0.5 and 30 seconds long".to_string());
+        }
+        if crossfade <= 0.0 || crossfade >= length / 2.0 {
+            return Err("Crossfade must be longer than 0 and shorter than half a segment".to_string());
+        }
+        Ok(HighlightPlan { starts, length, crossfade })
+    }
+
+    /// Length of the finished teaser
+    pub fn total_duration(&self) -> f64 {
+        let count = self.starts.len() as f64;
+        count * self.length - (count - 1.0) * self.crossfade
+    }
+}
+
+/// Segment starts centred in evenly sized slices of the file
+pub fn even_segment_starts(count: u32, length: f64, duration: f64) -> Vec<f64> {
+    (0..count)
+        .map(|i| {
+            let centre = duration * (i as f64 + 0.5) / count as f64;
+            clamp_start(centre - length / 2.0, length, duration)
+        })
+        .collect()
+}
+
+/// Segment starts at scene changes, spread evenly over the detected scenes
+pub fn scene_segment_starts(scene_starts: &[f64], count: u32, length: f64, duration: f64) -> Vec<f64> {
+    let count = count as usize;
+    let picked: Vec<f64> = if scene_starts.len() <= count {
+        scene_starts.to_vec()
+    } else {
+        (0..count)
+            .map(|i| scene_starts[i * scene_starts.len() / count])
+            .collect()
+    };
+
+    // Start just after the cut so the segment doesn't open on the previous shot's last frame
+    picked
+        .into_iter()
+        .map(|start| clamp_start(start + 0.1, length, duration))
+        .collect()
+}
+
+fn clamp_start(start: f64, length: f64, duration: f64) -> f64 {
+    start.min(duration - length).max(0.0)
+}
+
+/// Read each segment as its own seeked input (the main input is the first one)
+/// and join them with `xfade`/`acrossfade`. Every input is autorotated like the main one.
+pub fn apply_highlights(
+    graph: &mut FilterGraph,
+    input_path: &str,
+    plan: &HighlightPlan,
+    fps: f64,
+    has_audio: bool,
+) {
+    for option in segment_options(plan.starts[0], plan.length) {
+        graph.add_input_option(option);
+    }
+
+    let mut indices = vec![0];
+    for start in &plan.starts[1..] {
+        indices.push(graph.add_input_with_options(input_path, segment_options(*start, plan.length)));
+    }
+
+    // xfade needs identical frame rates and time bases on both sides
+    let prepare_video = format!("fps={},settb=AVTB,setpts=PTS-STARTPTS,format=yuv420p", fps);
+    let mut video = Vec::new();
+    let mut audio = Vec::new();
+    for (segment, index) in indices.iter().enumerate() {
+        let input = if segment == 0 { "{in}".to_string() } else { format!("{}:v", index) };
+        video.push(format!("[{}]{}[hlv{}]", input, prepare_video, segment));
+        let input = if segment == 0 { "{in}".to_string() } else { format!("{}:a", index) };
+        audio.push(format!("[{}]asetpts=PTS-STARTPTS[hla{}]", input, segment));
+    }
+
+    let mut video_current = "hlv0".to_string();
+    let mut audio_current = "hla0".to_string();
+    let last = indices.len() - 1;
+    for segment in 1..=last {
+        let video_out = if segment == last { "{out}".to_string() } else { format!("hlvx{}", segment) };
+        let audio_out = if segment == last { "{out}".to_string() } else { format!("hlax{}", segment) };
+        let offset = segment as f64 * (plan.length - plan.crossfade);
+
+        video.push(format!(
+            "[{}][hlv{}]xfade=transition=fade:duration={}:offset={:.3}[{}]",
+            video_current, segment, plan.crossfade, offset, video_out
+        ));
+        audio.push(format!(
+            "[{}][hla{}]acrossfade=d={}[{}]",
+            audio_current, segment, plan.crossfade, audio_out
+        ));
+        video_current = video_out;
+        audio_current = audio_out;
+    }
+
+    graph.video_graph(video.join(";"));
+    if has_audio {
+        graph.audio_graph(audio.join(";"));
+    }
+}
+
+fn segment_options(start: f64, length: f64) -> Vec<String> {
+    vec![
+        "-ss".to_string(),
+        format!("{:.3}", start),
+        "-t".to_string(),
+        format!("{:.3}", length),
+    ]
+}
@@ -0,0 +1,122 @@
+use serde::Serialize;
+use tauri::AppHandle;
+use crate::utils::ffmpeg::{self, FFmpegError};
+use crate::utils::filter_graph::FilterGraph;
+
+/// Number of frames `idet` looks at
+const SAMPLE_FRAMES: u32 = 300;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct InterlaceAnalysis {
+    pub tff: u64, // Top field first frames
+    pub bff: u64, // Bottom field first frames
+    pub progressive: u64,
+    pub undetermined: u64,
+    pub is_interlaced: bool,
+    pub field_order: Option<String>, // "tff" or "bff" when interlaced
+}
+
+/// Run `idet` on a sample of frames and count the field order it detects
+pub fn analyze_interlace(
+    app_handle: &AppHandle,
+    input_path: &str,
+    duration: Option<f64>,
+) -> Result<InterlaceAnalysis, FFmpegError> {
+    // Skip into the file a bit so intros and black frames don't dominate the sample
+    let start = duration.map(|d| (d * 0.1).min(60.0)).unwrap_or(0.0);
+
+    let output = ffmpeg::read_ffmpeg_output(
+        app_handle,
+        vec![
+            "-hide_banner".to_string(),
+            "-ss".to_string(),
+            format!("{:.2}", start),
+            "-i".to_string(),
+            input_path.to_string(),
+            "-vf".to_string(),
+            "idet".to_string(),
+            "-frames:v".to_string(),
+            SAMPLE_FRAMES.to_string(),
+            "-an".to_string(),
+            "-f".to_string(),
+            "null".to_string(),
+            "-".to_string(),
+        ],
+    )?;
+
+    parse_idet_output(&output).ok_or_else(|| FFmpegError {
+        message: "Failed to analyse interlacing".to_string(),
+    })
+}
+
+/// Parse the `Multi frame detection` summary line printed by `idet`
+fn parse_idet_output(output: &str) -> Option<InterlaceAnalysis> {
+    let line = output.lines().rev().find(|line| line.contains("Multi frame detection:"))?;
+    let summary = &line[line.find("Multi frame detection:")? + 22..];
+
+    let count = |label: &str| -> Option<u64> {
+        let start = summary.find(label)? + label.len();
+        summary[start..].split_whitespace().next()?.parse().ok()
+    };
+
+    let tff = count("TFF:")?;
+    let bff = count("BFF:")?;
+    let progressive = count("Progressive:")?;
+    let undetermined = count("Undetermined:").unwrap_or(0);
+
+    // Treat the content as interlaced once a fifth of the classified frames show combing;
+    // deinterlacing a few progressive frames is harmless, missing interlaced ones is not
+    let interlaced = tff + bff;
+    let is_interlaced = interlaced > 0 && interlaced * 5 >= interlaced + progressive;
+    let field_order = if is_interlaced {
+        Some(if tff >= bff { "tff" } else { "bff" }.to_string())
+    } else {
+        None
+    };
+
+    Some(InterlaceAnalysis {
+        tff,
+        bff,
+        progressive,
+        undetermined,
+        is_interlaced,
+        field_order,
+    })
+}
+
+/// Lowercase and check a deinterlace mode, so it can be validated before the analysis runs
+pub fn parse_deinterlace_mode(mode: &str) -> Result<String, String> {
+    let mode = mode.to_lowercase();
+    match mode.as_str() {
+        "off" | "auto" | "yadif" | "bwdif" => Ok(mode),
+        other => Err(format!(
+            "Invalid deinterlace mode: {}. Use 'auto', 'off', 'yadif' or 'bwdif'",
+            other
+        )),
+    }
+}
+
+/// Add a deinterlacing filter for the requested mode ("auto", "off", "yadif" or "bwdif").
+/// `auto` only deinterlaces when the analysis found interlaced content.
+pub fn apply_deinterlace(
+    graph: &mut FilterGraph,
+    mode: &str,
+    analysis: Option<&InterlaceAnalysis>,
+) -> Result<(), String> {
+    let parity = analysis
+        .and_then(|a| a.field_order.as_deref())
+        .unwrap_or("auto");
+
+    match parse_deinterlace_mode(mode)?.as_str() {
+        "off" => {}
+        "auto" => {
+            if analysis.is_some_and(|a| a.is_interlaced) {
+                graph.video(format!("bwdif=mode=send_frame:parity={}", parity));
+            }
+        }
+        "yadif" => graph.video(format!("yadif=mode=send_frame:parity={}", parity)),
+        _ => graph.video(format!("bwdif=mode=send_frame:parity={}", parity)),
+    }
+
+    Ok(())
+}
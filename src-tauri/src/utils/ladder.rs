@@ -0,0 +1,279 @@
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+pub const DEFAULT_SEGMENT_DURATION: f64 = 4.0;
+
+/// One rung of a bitrate ladder
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Rendition {
+    pub height: u32, // Short side in pixels (even), so portrait sources get the same ladder
+    pub video_bitrate: u32, // kbit/s
+    pub audio_bitrate: Option<u32>, // kbit/s, default 128
+    pub name: Option<String>, // Default "<height>p", also the rendition's folder name
+}
+
+/// A rung that fits the source, with its output size worked out
+#[derive(Debug, Clone, Serialize)]
+pub struct PlannedRendition {
+    pub name: String,
+    pub width: u32,
+    pub height: u32,
+    pub video_bitrate: u32,
+    pub audio_bitrate: u32,
+}
+
+pub fn default_ladder() -> Vec<Rendition> {
+    [(1080, 5000, 128), (720, 2800, 128), (480, 1400, 96), (360, 800, 96)]
+        .into_iter()
+        .map(|(height, video_bitrate, audio_bitrate)| Rendition {
+            height,
+            video_bitrate,
+            audio_bitrate: Some(audio_bitrate),
+            name: None,
+        })
+        .collect()
+}
+
+/// Sizes for the rungs no taller than the (displayed) source, highest first. The
+/// names of skipped rungs are returned separately. When every rung is above the
+/// source, the lowest one is kept at the source size so there is still an output.
+pub fn plan_renditions(
+    ladder: &[Rendition],
+    source_width: u32,
+    source_height: u32,
+) -> Result<(Vec<PlannedRendition>, Vec<String>), String> {
+    let portrait = source_height > source_width;
+    let (short_side, long_side) = if portrait {
+        (source_width, source_height)
+    } else {
+        (source_height, source_width)
+    };
+
+    let mut rungs: Vec<&Rendition> = ladder.iter().collect();
+    rungs.sort_by(|a, b| b.height.cmp(&a.height).then(b.video_bitrate.cmp(&a.video_bitrate)));
+
+    let mut planned = Vec::new();
+    let mut skipped = Vec::new();
+    let mut names = Vec::new();
+    for rung in &rungs {
+        if rung.height < 64 || rung.video_bitrate < 50 {
+            return Err("Renditions must be at least 64 pixels and 50 kbit/s".to_string());
+        }
+        // H.264 with 4:2:0 chroma needs even dimensions
+        if rung.height % 2 != 0 {
+            return Err(format!("Rendition height must be even: {}", rung.height));
+        }
+        let name = rung.name.clone().unwrap_or_else(|| format!("{}p", rung.height));
+        if !is_valid_name(&name) {
+            return Err(format!("Invalid rendition name: {}", name));
+        }
+        if names.contains(&name) {
+            return Err(format!("Duplicate rendition name: {}", name));
+        }
+        names.push(name.clone());
+
+        if rung.height > short_side {
+            skipped.push(name);
+            continue;
+        }
+        planned.push(plan(rung, name, rung.height, short_side, long_side, portrait));
+    }
+
+    if planned.is_empty() {
+        let lowest = rungs.last().ok_or_else(|| "The bitrate ladder is empty".to_string())?;
+        let height = (short_side - short_side % 2).max(2);
+        skipped.pop();
+        let name = lowest.name.clone().unwrap_or_else(|| format!("{}p", height));
+        planned.push(plan(lowest, name, height, short_side, long_side, portrait));
+    }
+
+    Ok((planned, skipped))
+}
+
+fn plan(rung: &Rendition, name: String, short: u32, source_short: u32, source_long: u32, portrait: bool) -> PlannedRendition {
+    let long = ((source_long as f64 * short as f64 / source_short as f64 / 2.0).round() as u32 * 2).max(2);
+    let (width, height) = if portrait { (short, long) } else { (long, short) };
+    PlannedRendition {
+        name,
+        width,
+        height,
+        video_bitrate: rung.video_bitrate,
+        audio_bitrate: rung.audio_bitrate.unwrap_or(128),
+    }
+}
+
+/// Used in folder names and FFmpeg's `var_stream_map`, so keep it plain
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+/// Split the video once and scale a copy for every rendition, `[v0]`, `[v1]`, ...
+pub fn ladder_filter(renditions: &[PlannedRendition]) -> String {
+    let mut graph = format!("[0:v]split={}", renditions.len());
+    for i in 0..renditions.len() {
+        graph.push_str(&format!("[s{}]", i));
+    }
+    for (i, rendition) in renditions.iter().enumerate() {
+        graph.push_str(&format!(
+            ";[s{i}]scale={}:{},setsar=1[v{i}]",
+            rendition.width,
+            rendition.height,
+            i = i
+        ));
+    }
+    graph
+}
+
+/// Output options encoding every rendition with keyframes on the same frames, so
+/// players can switch between them at any segment boundary. `audio_streams` is the
+/// number of audio outputs mapped (one per rendition for HLS, one for DASH).
+pub fn encode_args(
+    renditions: &[PlannedRendition],
+    fps: Option<f64>,
+    segment_duration: f64,
+    audio_streams: usize,
+) -> Vec<String> {
+    let mut args = vec![
+        "-filter_complex".to_string(),
+        ladder_filter(renditions),
+    ];
+    for i in 0..renditions.len() {
+        args.push("-map".to_string());
+        args.push(format!("[v{}]", i));
+    }
+    for _ in 0..audio_streams {
+        args.push("-map".to_string());
+        args.push("0:a:0".to_string());
+    }
+
+    args.extend(vec![
+        "-c:v".to_string(),
+        "libx264".to_string(),
+        "-preset".to_string(),
+        "medium".to_string(),
+        "-profile:v".to_string(),
+        "high".to_string(),
+        "-pix_fmt".to_string(),
+        "yuv420p".to_string(),
+        // Keyframes only where forced, at every segment boundary
+        "-sc_threshold".to_string(),
+        "0".to_string(),
+        "-force_key_frames".to_string(),
+        format!("expr:gte(t,n_forced*{})", segment_duration),
+    ]);
+    if let Some(fps) = fps {
+        let gop = (fps * segment_duration).round().max(1.0) as u32;
+        args.extend(vec![
+            "-g".to_string(),
+            gop.to_string(),
+            "-keyint_min".to_string(),
+            gop.to_string(),
+        ]);
+    }
+    for (i, rendition) in renditions.iter().enumerate() {
+        args.extend(vec![
+            format!("-b:v:{}", i),
+            format!("{}k", rendition.video_bitrate),
+            format!("-maxrate:v:{}", i),
+            format!("{}k", rendition.video_bitrate * 107 / 100),
+            format!("-bufsize:v:{}", i),
+            format!("{}k", rendition.video_bitrate * 3 / 2),
+        ]);
+    }
+
+    if audio_streams > 0 {
+        args.extend(vec![
+            "-c:a".to_string(),
+            "aac".to_string(),
+            "-ac".to_string(),
+            "2".to_string(),
+        ]);
+        for i in 0..audio_streams {
+            // A single DASH audio track gets the best rung's bitrate
+            let bitrate = renditions.get(i).unwrap_or(&renditions[0]).audio_bitrate;
+            args.push(format!("-b:a:{}", i));
+            args.push(format!("{}k", bitrate));
+        }
+    }
+    args
+}
+
+/// HLS muxer options: one folder per rendition with its playlist and segments,
+/// and `master.m3u8` next to the folders
+pub fn hls_args(renditions: &[PlannedRendition], has_audio: bool, segment_type: &str, segment_duration: f64, dir: &Path) -> Vec<String> {
+    let stream_map = renditions
+        .iter()
+        .enumerate()
+        .map(|(i, rendition)| {
+            if has_audio {
+                format!("v:{i},a:{i},name:{}", rendition.name, i = i)
+            } else {
+                format!("v:{},name:{}", i, rendition.name)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ");
+    let segment_extension = if segment_type == "fmp4" { "m4s" } else { "ts" };
+
+    let mut args = vec![
+        "-f".to_string(),
+        "hls".to_string(),
+        "-hls_time".to_string(),
+        segment_duration.to_string(),
+        "-hls_playlist_type".to_string(),
+        "vod".to_string(),
+        "-hls_flags".to_string(),
+        "independent_segments".to_string(),
+        "-hls_segment_type".to_string(),
+        segment_type.to_string(),
+    ];
+    if segment_type == "fmp4" {
+        args.push("-hls_fmp4_init_filename".to_string());
+        args.push("init.mp4".to_string());
+    }
+    args.extend(vec![
+        "-master_pl_name".to_string(),
+        "master.m3u8".to_string(),
+        "-var_stream_map".to_string(),
+        stream_map,
+        "-hls_segment_filename".to_string(),
+        dir.join("%v").join(format!("seg_%05d.{}", segment_extension)).to_string_lossy().to_string(),
+        "-y".to_string(),
+        dir.join("%v").join("index.m3u8").to_string_lossy().to_string(),
+    ]);
+    args
+}
+
+/// DASH muxer options writing `manifest.mpd` with the video renditions in one
+/// adaptation set. With `hls_playlists` the same fMP4 segments also get HLS playlists.
+pub fn dash_args(has_audio: bool, segment_duration: f64, hls_playlists: bool, dir: &Path) -> Vec<String> {
+    let adaptation_sets = if has_audio {
+        "id=0,streams=v id=1,streams=a"
+    } else {
+        "id=0,streams=v"
+    };
+
+    let mut args = vec![
+        "-f".to_string(),
+        "dash".to_string(),
+        "-seg_duration".to_string(),
+        segment_duration.to_string(),
+        "-use_template".to_string(),
+        "1".to_string(),
+        "-use_timeline".to_string(),
+        "1".to_string(),
+        "-adaptation_sets".to_string(),
+        adaptation_sets.to_string(),
+        "-init_seg_name".to_string(),
+        "init_$RepresentationID$.m4s".to_string(),
+        "-media_seg_name".to_string(),
+        "chunk_$RepresentationID$_$Number%05d$.m4s".to_string(),
+    ];
+    if hls_playlists {
+        args.push("-hls_playlist".to_string());
+        args.push("1".to_string());
+    }
+    args.push("-y".to_string());
+    args.push(dir.join("manifest.mpd").to_string_lossy().to_string());
+    args
+}
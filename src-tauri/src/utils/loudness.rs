@@ -0,0 +1,198 @@
+use serde::{Deserialize, Serialize};
+use std::process::Child;
+use std::sync::{Arc, Mutex};
+use tauri::AppHandle;
+use crate::utils::ffmpeg::{self, FFmpegError};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoudnessOptions {
+    pub preset: Option<String>, // "streaming" (-14 LUFS, default), "podcast" (-16 LUFS) or "broadcast" (-23 LUFS, EBU R128)
+    pub integrated: Option<f64>, // Target integrated loudness in LUFS, overrides the preset
+    pub true_peak: Option<f64>, // Maximum true peak in dBTP
+    pub lra: Option<f64>, // Target loudness range in LU
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct LoudnessTarget {
+    pub integrated: f64,
+    pub true_peak: f64,
+    pub lra: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LoudnessMeasurement {
+    pub integrated: f64, // LUFS
+    pub true_peak: f64, // dBTP
+    pub lra: f64, // LU
+    pub threshold: f64, // LUFS
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LoudnessReport {
+    pub target: LoudnessTarget,
+    pub before: LoudnessMeasurement,
+    pub after: Option<LoudnessMeasurement>,
+}
+
+/// Values printed by `loudnorm` with `print_format=json` (all as strings)
+#[derive(Debug, Deserialize)]
+struct LoudnormJson {
+    input_i: String,
+    input_tp: String,
+    input_lra: String,
+    input_thresh: String,
+    output_i: String,
+    output_tp: String,
+    output_lra: String,
+    output_thresh: String,
+    target_offset: String,
+}
+
+/// Measurement from the first pass, kept to configure the second pass
+#[derive(Debug, Clone)]
+pub struct FirstPass {
+    pub measurement: LoudnessMeasurement,
+    target_offset: f64,
+}
+
+impl LoudnessOptions {
+    pub fn target(&self) -> Result<LoudnessTarget, String> {
+        let preset = self.preset.as_deref().unwrap_or("streaming").to_lowercase();
+        let mut target = match preset.as_str() {
+            "streaming" => LoudnessTarget { integrated: -14.0, true_peak: -1.0, lra: 11.0 },
+            "podcast" => LoudnessTarget { integrated: -16.0, true_peak: -1.5, lra: 11.0 },
+            "broadcast" => LoudnessTarget { integrated: -23.0, true_peak: -1.0, lra: 7.0 },
+            other => {
+                return Err(format!(
+                    "Invalid loudness preset: {}. Use 'streaming', 'podcast' or 'broadcast'",
+                    other
+                ))
+            }
+        };
+
+        if let Some(integrated) = self.integrated {
+            target.integrated = integrated;
+        }
+        if let Some(true_peak) = self.true_peak {
+            target.true_peak = true_peak;
+        }
+        if let Some(lra) = self.lra {
+            target.lra = lra;
+        }
+
+        // Ranges accepted by the loudnorm filter
+        if !(-70.0..=-5.0).contains(&target.integrated) {
+            return Err("Target loudness must be between -70 and -5 LUFS".to_string());
+        }
+        if !(-9.0..=0.0).contains(&target.true_peak) {
+            return Err("True peak must be between -9 and 0 dBTP".to_string());
+        }
+        if !(1.0..=50.0).contains(&target.lra) {
+            return Err("Loudness range must be between 1 and 50 LU".to_string());
+        }
+
+        Ok(target)
+    }
+}
+
+/// First pass: measure the loudness of the input's audio after `pre_filters`
+/// (the processing that runs ahead of `loudnorm` in the second pass)
+pub fn measure_loudness(
+    app_handle: &AppHandle,
+    input_path: &str,
+    pre_filters: &[String],
+    target: &LoudnessTarget,
+    process_state: Arc<Mutex<Option<Child>>>,
+    duration: Option<f64>,
+    progress_range: (f64, f64),
+) -> Result<FirstPass, FFmpegError> {
+    let mut filters = pre_filters.to_vec();
+    filters.push(format!(
+        "loudnorm=I={}:TP={}:LRA={}:print_format=json",
+        target.integrated, target.true_peak, target.lra
+    ));
+    let args = vec![
+        "-hide_banner".to_string(),
+        "-i".to_string(),
+        input_path.to_string(),
+        "-vn".to_string(),
+        "-af".to_string(),
+        filters.join(","),
+        "-f".to_string(),
+        "null".to_string(),
+        "-".to_string(),
+    ];
+
+    let log = ffmpeg::execute_ffmpeg_capture(
+        app_handle,
+        args,
+        "conversion-progress",
+        process_state,
+        duration,
+        progress_range,
+    )?;
+
+    let json = parse_loudnorm_json(&log).ok_or_else(|| FFmpegError {
+        message: "Failed to read loudness measurement".to_string(),
+    })?;
+
+    let measurement = LoudnessMeasurement {
+        integrated: parse_value(&json.input_i),
+        true_peak: parse_value(&json.input_tp),
+        lra: parse_value(&json.input_lra),
+        threshold: parse_value(&json.input_thresh),
+    };
+    if !measurement.integrated.is_finite() {
+        return Err(FFmpegError {
+            message: "Audio is silent, there is nothing to normalize".to_string(),
+        });
+    }
+
+    Ok(FirstPass {
+        measurement,
+        target_offset: parse_value(&json.target_offset),
+    })
+}
+
+/// Second pass filter: linear normalization using the first pass measurement.
+/// `loudnorm` works at 192 kHz internally, so the audio is resampled back afterwards.
+pub fn normalization_filters(target: &LoudnessTarget, first_pass: &FirstPass, sample_rate: u32) -> Vec<String> {
+    let measured = &first_pass.measurement;
+    vec![
+        format!(
+            "loudnorm=I={}:TP={}:LRA={}:measured_I={}:measured_TP={}:measured_LRA={}:measured_thresh={}:offset={}:linear=true:print_format=json",
+            target.integrated,
+            target.true_peak,
+            target.lra,
+            measured.integrated,
+            measured.true_peak,
+            measured.lra,
+            measured.threshold,
+            first_pass.target_offset,
+        ),
+        format!("aresample={}", sample_rate),
+    ]
+}
+
+/// Read the result of the second pass from its log output
+pub fn parse_normalized_loudness(log: &str) -> Option<LoudnessMeasurement> {
+    let json = parse_loudnorm_json(log)?;
+    Some(LoudnessMeasurement {
+        integrated: parse_value(&json.output_i),
+        true_peak: parse_value(&json.output_tp),
+        lra: parse_value(&json.output_lra),
+        threshold: parse_value(&json.output_thresh),
+    })
+}
+
+/// The JSON block is the last `{ ... }` in the log
+fn parse_loudnorm_json(log: &str) -> Option<LoudnormJson> {
+    let start = log.rfind('{')?;
+    let end = log[start..].find('}')? + start;
+    serde_json::from_str(&log[start..=end]).ok()
+}
+
+/// loudnorm reports silence as "-inf"
+fn parse_value(value: &str) -> f64 {
+    value.trim().parse().unwrap_or(f64::NEG_INFINITY)
+}
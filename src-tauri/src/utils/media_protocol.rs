@@ -0,0 +1,248 @@
+use serde::Serialize;
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::http::{header, Method, Request, Response, StatusCode};
+use tauri::{AppHandle, Manager};
+use crate::commands::settings;
+use crate::utils::cache::SourceStamp;
+use crate::utils::media_type;
+
+/// URI scheme the webview loads media from, e.g. `convertFileSrc(path, "ripley")`
+pub const SCHEME: &str = "ripley";
+
+/// Largest body returned for a single range request; players ask for the rest with further ranges
+const MAX_CHUNK: u64 = 4 * 1024 * 1024;
+
+/// Files outside the workspace that the user opened, and temporary folders
+/// (e.g. live transcodes), that may be served
+#[derive(Default)]
+pub struct MediaAccess {
+    allowed: Mutex<HashSet<PathBuf>>,
+    allowed_dirs: Mutex<HashSet<PathBuf>>,
+}
+
+impl MediaAccess {
+    pub fn allow(&self, path: &Path) {
+        if let Ok(path) = path.canonicalize() {
+            self.allowed.lock().unwrap().insert(path);
+        }
+    }
+
+    /// Allow every file inside a folder, including ones created later
+    pub fn allow_dir(&self, dir: &Path) {
+        if let Ok(dir) = dir.canonicalize() {
+            self.allowed_dirs.lock().unwrap().insert(dir);
+        }
+    }
+
+    pub fn revoke_dir(&self, dir: &Path) {
+        if let Ok(dir) = dir.canonicalize() {
+            self.allowed_dirs.lock().unwrap().remove(&dir);
+        }
+    }
+
+    fn is_allowed(&self, path: &Path) -> bool {
+        self.allowed.lock().unwrap().contains(path)
+            || self.allowed_dirs.lock().unwrap().iter().any(|dir| path.starts_with(dir))
+    }
+}
+
+/// A file handed to the webview as a protocol URL instead of inline data, with its
+/// type carried alongside so the frontend can pick an `<img>` or `<video>` element
+#[derive(Debug, Serialize)]
+pub struct MediaFile {
+    pub path: String, // For `convertFileSrc(path, "ripley")`
+    pub mime_type: String,
+    pub size: u64,
+    pub modified_ms: u64, // Changes when the file is rewritten, to bust the webview's cache
+}
+
+/// Allow a file to be served and describe it for the frontend
+pub fn media_file(media_access: &MediaAccess, path: &Path) -> Result<MediaFile, String> {
+    if !path.is_file() {
+        return Err("File does not exist".to_string());
+    }
+    media_access.allow(path);
+    let stamp = SourceStamp::read(path)?;
+
+    Ok(MediaFile {
+        path: path.to_string_lossy().replace('\\', "/"),
+        mime_type: content_type(path).to_string(),
+        size: stamp.size,
+        modified_ms: stamp.modified_ms,
+    })
+}
+
+/// Serve a file for a `ripley://localhost/<percent-encoded path>` request,
+/// honouring `Range` so media elements can seek without loading the whole file
+pub fn handle_request(app: &AppHandle, request: &Request<Vec<u8>>) -> Response<Vec<u8>> {
+    let path = match request_path(request) {
+        Some(path) => path,
+        None => return error_response(StatusCode::BAD_REQUEST),
+    };
+    let path = match path.canonicalize() {
+        Ok(path) if path.is_file() => path,
+        _ => return error_response(StatusCode::NOT_FOUND),
+    };
+    if !is_servable(app, &path) {
+        return error_response(StatusCode::FORBIDDEN);
+    }
+
+    match serve_file(&path, request) {
+        Ok(response) => response,
+        Err(_) => error_response(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+fn serve_file(path: &Path, request: &Request<Vec<u8>>) -> std::io::Result<Response<Vec<u8>>> {
+    let mut file = File::open(path)?;
+    let file_len = file.metadata()?.len();
+
+    let range = request
+        .headers()
+        .get(header::RANGE)
+        .and_then(|value| value.to_str().ok());
+    let (status, start, end) = match range {
+        Some(range) => match parse_range(range, file_len) {
+            Some((start, end)) => (StatusCode::PARTIAL_CONTENT, start, end.min(start + MAX_CHUNK - 1)),
+            None => {
+                return Ok(Response::builder()
+                    .status(StatusCode::RANGE_NOT_SATISFIABLE)
+                    .header(header::CONTENT_RANGE, format!("bytes */{}", file_len))
+                    .body(Vec::new())
+                    .unwrap());
+            }
+        },
+        // Without a range the client expects the whole file (`fetch()`, `<img>`)
+        None => (StatusCode::OK, 0, file_len.saturating_sub(1)),
+    };
+
+    let length = if file_len == 0 { 0 } else { end - start + 1 };
+    let mut body = Vec::new();
+    if request.method() != Method::HEAD && length > 0 {
+        // Read straight into the body, without zero-filling it first
+        body.reserve_exact(length as usize);
+        file.seek(SeekFrom::Start(start))?;
+        file.take(length).read_to_end(&mut body)?;
+        if (body.len() as u64) < length {
+            return Err(std::io::ErrorKind::UnexpectedEof.into());
+        }
+    }
+
+    let mut response = Response::builder()
+        .status(status)
+        .header(header::CONTENT_TYPE, content_type(path))
+        .header(header::CONTENT_LENGTH, length)
+        .header(header::ACCEPT_RANGES, "bytes")
+        .header(header::ACCESS_CONTROL_ALLOW_ORIGIN, "*");
+    if status == StatusCode::PARTIAL_CONTENT {
+        response = response.header(header::CONTENT_RANGE, format!("bytes {}-{}/{}", start, end, file_len));
+    }
+
+    Ok(response.body(body).unwrap())
+}
+
+/// Workspace files are always servable, other files once the user has opened them
+fn is_servable(app: &AppHandle, path: &Path) -> bool {
+    let in_workspace = settings::workspace_dir(app)
+        .ok()
+        .and_then(|workspace| workspace.canonicalize().ok())
+        .is_some_and(|workspace| path.starts_with(workspace));
+
+    in_workspace || app.state::<MediaAccess>().is_allowed(path)
+}
+
+/// The file path is the percent-encoded URI path (after the leading `/`)
+fn request_path(request: &Request<Vec<u8>>) -> Option<PathBuf> {
+    let encoded = request.uri().path().trim_start_matches('/');
+    let decoded = percent_decode(encoded)?;
+    if decoded.is_empty() {
+        return None;
+    }
+    Some(PathBuf::from(decoded))
+}
+
+fn percent_decode(value: &str) -> Option<String> {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = value.get(i + 1..i + 3)?;
+            decoded.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(decoded).ok()
+}
+
+/// Parse a single `bytes=start-end`, `bytes=start-` or `bytes=-suffix` range (inclusive end)
+fn parse_range(value: &str, file_len: u64) -> Option<(u64, u64)> {
+    let spec = value.trim().strip_prefix("bytes=")?;
+    // Multipart ranges are not supported, serve the first one
+    let spec = spec.split(',').next()?.trim();
+    let (start, end) = spec.split_once('-')?;
+
+    let (start, end) = if start.is_empty() {
+        let suffix: u64 = end.parse().ok()?;
+        if suffix == 0 {
+            return None;
+        }
+        (file_len.saturating_sub(suffix), file_len.checked_sub(1)?)
+    } else {
+        let start: u64 = start.parse().ok()?;
+        let end = if end.is_empty() {
+            file_len.checked_sub(1)?
+        } else {
+            end.parse::<u64>().ok()?.min(file_len.checked_sub(1)?)
+        };
+        (start, end)
+    };
+
+    if start > end || start >= file_len {
+        return None;
+    }
+    Some((start, end))
+}
+
+/// MIME type from the file's content, or from its extension for formats without a
+/// signature (playlists, subtitles, fragments)
+pub fn content_type(path: &Path) -> &'static str {
+    if let Some(format) = media_type::detect_file(path) {
+        return format.mime_type();
+    }
+
+    match media_type::extension_of(path).as_str() {
+        "mp4" | "m4v" => "video/mp4",
+        "webm" => "video/webm",
+        "ogg" | "ogv" => "video/ogg",
+        "mov" => "video/quicktime",
+        "mkv" => "video/x-matroska",
+        "avi" => "video/x-msvideo",
+        "m4a" => "audio/mp4",
+        "mp3" => "audio/mpeg",
+        "wav" => "audio/wav",
+        "jpg" | "jpeg" => "image/jpeg",
+        "png" => "image/png",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "vtt" => "text/vtt",
+        "m3u8" => "application/vnd.apple.mpegurl",
+        "m4s" => "video/iso.segment",
+        _ => "application/octet-stream",
+    }
+}
+
+fn error_response(status: StatusCode) -> Response<Vec<u8>> {
+    Response::builder()
+        .status(status)
+        .header(header::ACCESS_CONTROL_ALLOW_ORIGIN, "*")
+        .body(Vec::new())
+        .unwrap()
+}
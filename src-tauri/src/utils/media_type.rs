@@ -0,0 +1,263 @@
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+/// Bytes read from the start of a file; enough for the EBML header and most ftyp boxes
+const SNIFF_LEN: usize = 4096;
+
+const ASF_GUID: [u8; 16] = [
+    0x30, 0x26, 0xB2, 0x75, 0x8E, 0x66, 0xCF, 0x11, 0xA6, 0xD9, 0x00, 0xAA, 0x00, 0x62, 0xCE, 0x6C,
+];
+
+/// Container or image format recognised from a file's leading bytes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MediaFormat {
+    Mp4,
+    M4a,
+    Mov,
+    ThreeGp,
+    Matroska,
+    WebM,
+    Avi,
+    Wav,
+    Ogg, // Ogg with a Theora stream
+    OggAudio,
+    Flv,
+    Asf,
+    Jpeg,
+    Png,
+    Gif,
+    WebP,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MediaKind {
+    Video,
+    Audio,
+    Image,
+}
+
+impl MediaFormat {
+    pub fn mime_type(self) -> &'static str {
+        match self {
+            MediaFormat::Mp4 => "video/mp4",
+            MediaFormat::M4a => "audio/mp4",
+            MediaFormat::Mov => "video/quicktime",
+            MediaFormat::ThreeGp => "video/3gpp",
+            MediaFormat::Matroska => "video/x-matroska",
+            MediaFormat::WebM => "video/webm",
+            MediaFormat::Avi => "video/x-msvideo",
+            MediaFormat::Wav => "audio/wav",
+            MediaFormat::Ogg => "video/ogg",
+            MediaFormat::OggAudio => "audio/ogg",
+            MediaFormat::Flv => "video/x-flv",
+            MediaFormat::Asf => "video/x-ms-asf",
+            MediaFormat::Jpeg => "image/jpeg",
+            MediaFormat::Png => "image/png",
+            MediaFormat::Gif => "image/gif",
+            MediaFormat::WebP => "image/webp",
+        }
+    }
+
+    pub fn kind(self) -> MediaKind {
+        match self {
+            MediaFormat::M4a | MediaFormat::Wav | MediaFormat::OggAudio => MediaKind::Audio,
+            MediaFormat::Jpeg | MediaFormat::Png | MediaFormat::Gif | MediaFormat::WebP => MediaKind::Image,
+            _ => MediaKind::Video,
+        }
+    }
+
+    /// Extensions files of this format usually have, the preferred one first
+    pub fn extensions(self) -> &'static [&'static str] {
+        match self {
+            MediaFormat::Mp4 => &["mp4", "m4v"],
+            MediaFormat::M4a => &["m4a", "m4b", "mp4"],
+            MediaFormat::Mov => &["mov", "qt"],
+            MediaFormat::ThreeGp => &["3gp", "3g2"],
+            // WebM is a Matroska subset, so .mkv files may carry the webm DocType and vice versa
+            MediaFormat::Matroska => &["mkv", "mka", "webm"],
+            MediaFormat::WebM => &["webm", "mkv"],
+            MediaFormat::Avi => &["avi"],
+            MediaFormat::Wav => &["wav"],
+            MediaFormat::Ogg => &["ogv", "ogg"],
+            MediaFormat::OggAudio => &["ogg", "oga", "opus"],
+            MediaFormat::Flv => &["flv"],
+            MediaFormat::Asf => &["wmv", "asf", "wma"],
+            MediaFormat::Jpeg => &["jpg", "jpeg"],
+            MediaFormat::Png => &["png"],
+            MediaFormat::Gif => &["gif"],
+            MediaFormat::WebP => &["webp"],
+        }
+    }
+
+    /// Whether a (lowercase) extension is usual for this format
+    pub fn matches_extension(self, extension: &str) -> bool {
+        self.extensions().contains(&extension)
+    }
+}
+
+/// Detect the format of a file from its leading bytes
+pub fn detect_file(path: &Path) -> Option<MediaFormat> {
+    let mut file = File::open(path).ok()?;
+    let mut header = Vec::with_capacity(SNIFF_LEN);
+    file.by_ref().take(SNIFF_LEN as u64).read_to_end(&mut header).ok()?;
+    detect(&header)
+}
+
+/// Detect a format from the start of a file
+pub fn detect(header: &[u8]) -> Option<MediaFormat> {
+    if header.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        return Some(MediaFormat::Jpeg);
+    }
+    if header.starts_with(&[0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A]) {
+        return Some(MediaFormat::Png);
+    }
+    if header.starts_with(b"GIF87a") || header.starts_with(b"GIF89a") {
+        return Some(MediaFormat::Gif);
+    }
+    if header.starts_with(b"RIFF") && header.len() >= 12 {
+        return match &header[8..12] {
+            b"AVI " => Some(MediaFormat::Avi),
+            b"WAVE" => Some(MediaFormat::Wav),
+            b"WEBP" => Some(MediaFormat::WebP),
+            _ => None,
+        };
+    }
+    if header.starts_with(&[0x1A, 0x45, 0xDF, 0xA3]) {
+        return Some(match ebml_doc_type(header).as_deref() {
+            Some("webm") => MediaFormat::WebM,
+            _ => MediaFormat::Matroska,
+        });
+    }
+    if header.starts_with(b"OggS") {
+        // The first page holds the identification header of the first stream
+        let has_video = header.windows(7).any(|window| window == b"\x80theora");
+        return Some(if has_video { MediaFormat::Ogg } else { MediaFormat::OggAudio });
+    }
+    if header.starts_with(b"FLV\x01") {
+        return Some(MediaFormat::Flv);
+    }
+    if header.starts_with(&ASF_GUID) {
+        return Some(MediaFormat::Asf);
+    }
+    if header.len() >= 12 {
+        match &header[4..8] {
+            b"ftyp" => return Some(ftyp_format(header)),
+            // Old QuickTime files start with a movie or media atom instead of ftyp
+            b"moov" | b"mdat" | b"wide" | b"free" | b"skip" | b"pnot" => return Some(MediaFormat::Mov),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Lowercase extension of a path, empty if there is none
+pub fn extension_of(path: &Path) -> String {
+    path.extension()
+        .and_then(|s| s.to_str())
+        .unwrap_or("")
+        .to_lowercase()
+}
+
+/// Explain a disagreement between a file's extension and its detected format
+pub fn extension_warning(path: &Path, format: MediaFormat) -> Option<String> {
+    let extension = extension_of(path);
+    let expected = format.extensions()[0];
+    if extension.is_empty() {
+        return Some(format!("The file has no extension but contains {} data (.{})", format_name(format), expected));
+    }
+    if format.matches_extension(&extension) {
+        return None;
+    }
+    Some(format!(
+        "The file is named .{} but contains {} data (.{})",
+        extension,
+        format_name(format),
+        expected
+    ))
+}
+
+fn format_name(format: MediaFormat) -> &'static str {
+    match format {
+        MediaFormat::Mp4 => "MP4",
+        MediaFormat::M4a => "MPEG-4 audio",
+        MediaFormat::Mov => "QuickTime",
+        MediaFormat::ThreeGp => "3GP",
+        MediaFormat::Matroska => "Matroska",
+        MediaFormat::WebM => "WebM",
+        MediaFormat::Avi => "AVI",
+        MediaFormat::Wav => "WAV",
+        MediaFormat::Ogg => "Ogg video",
+        MediaFormat::OggAudio => "Ogg audio",
+        MediaFormat::Flv => "FLV",
+        MediaFormat::Asf => "ASF/WMV",
+        MediaFormat::Jpeg => "JPEG",
+        MediaFormat::Png => "PNG",
+        MediaFormat::Gif => "GIF",
+        MediaFormat::WebP => "WebP",
+    }
+}
+
+/// Classify an ISO base media file by its major brand, falling back to the compatible brands
+fn ftyp_format(header: &[u8]) -> MediaFormat {
+    let box_size = u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as usize;
+    let end = box_size.clamp(12, header.len());
+    let brands = std::iter::once(&header[8..12]).chain(
+        header
+            .get(16..end)
+            .unwrap_or(&[])
+            .chunks_exact(4),
+    );
+
+    for brand in brands {
+        match brand {
+            b"qt  " => return MediaFormat::Mov,
+            b"M4A " | b"M4B " | b"M4P " => return MediaFormat::M4a,
+            b"M4V " | b"M4VH" | b"M4VP" | b"isom" | b"iso2" | b"mp41" | b"mp42" | b"avc1" | b"dash" => {
+                return MediaFormat::Mp4
+            }
+            brand if brand.starts_with(b"3gp") || brand.starts_with(b"3g2") => return MediaFormat::ThreeGp,
+            _ => {}
+        }
+    }
+    MediaFormat::Mp4
+}
+
+/// Read the DocType ("matroska" or "webm") from the EBML header
+fn ebml_doc_type(header: &[u8]) -> Option<String> {
+    // Skip the EBML element ID and its size, then walk the header's children
+    let (header_size, size_len) = read_vint(header.get(4..)?)?;
+    let mut pos = 4 + size_len;
+    let end = (pos + header_size as usize).min(header.len());
+
+    while pos + 2 < end {
+        // Element IDs in the EBML header are two bytes long
+        let id = u16::from_be_bytes([header[pos], header[pos + 1]]);
+        let (size, size_len) = read_vint(&header[pos + 2..])?;
+        let data_start = pos + 2 + size_len;
+        let data_end = data_start.checked_add(size as usize)?;
+        if id == 0x4282 {
+            let value = header.get(data_start..data_end.min(header.len()))?;
+            return Some(String::from_utf8_lossy(value).trim_end_matches('\0').to_string());
+        }
+        pos = data_end;
+    }
+    None
+}
+
+/// Decode an EBML variable-length size, returning the value and its length in bytes
+fn read_vint(data: &[u8]) -> Option<(u64, usize)> {
+    let first = *data.first()?;
+    let len = first.leading_zeros() as usize + 1;
+    if len > 8 || data.len() < len {
+        return None;
+    }
+    let mut value = (first as u64) & (0xFF >> len);
+    for &byte in &data[1..len] {
+        value = (value << 8) | byte as u64;
+    }
+    Some((value, len))
+}
@@ -1,3 +1,27 @@
 pub mod ffmpeg;
 pub mod deep_filter;
-
+pub mod animation;
+pub mod audio_filters;
+pub mod audio_image;
+pub mod cache;
+pub mod comparison;
+pub mod contact_sheet;
+pub mod crf_search;
+pub mod filter_graph;
+pub mod watermark;
+pub mod frame_rate;
+pub mod highlights;
+pub mod interlace;
+pub mod ladder;
+pub mod loudness;
+pub mod media_protocol;
+pub mod media_type;
+pub mod peaks;
+pub mod probe;
+pub mod quality;
+pub mod rotation;
+pub mod scene;
+pub mod silence;
+pub mod speed;
+pub mod sprites;
+pub mod video_denoise;
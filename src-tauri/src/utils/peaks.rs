@@ -0,0 +1,90 @@
+/// Audio is mixed to mono and resampled to this rate before measuring peaks
+pub const PEAK_SAMPLE_RATE: u32 = 16000;
+
+const MAGIC: &[u8; 4] = b"RFPK";
+const FORMAT_VERSION: u32 = 1;
+
+/// Accumulates min/max/RMS per bucket while samples stream in
+pub struct PeakBuilder {
+    samples_per_bucket: u64,
+    count: u64,
+    min: f32,
+    max: f32,
+    sum_squares: f64,
+    peaks: Vec<[f32; 3]>,
+}
+
+impl PeakBuilder {
+    /// Size the buckets so `expected_samples` fill about `buckets` of them
+    pub fn new(expected_samples: u64, buckets: u32) -> Self {
+        PeakBuilder {
+            samples_per_bucket: expected_samples.div_ceil(buckets.max(1) as u64).max(1),
+            count: 0,
+            min: f32::MAX,
+            max: f32::MIN,
+            sum_squares: 0.0,
+            peaks: Vec::with_capacity(buckets as usize),
+        }
+    }
+
+    pub fn push(&mut self, samples: &[f32]) {
+        for &sample in samples {
+            self.min = self.min.min(sample);
+            self.max = self.max.max(sample);
+            self.sum_squares += (sample as f64) * (sample as f64);
+            self.count += 1;
+            if self.count == self.samples_per_bucket {
+                self.flush();
+            }
+        }
+    }
+
+    /// Close the last, partial bucket and encode the result, all values little-endian:
+    ///
+    /// | bytes | field                                  |
+    /// |-------|----------------------------------------|
+    /// | 0-3   | magic `RFPK`                           |
+    /// | 4-7   | u32 format version (1)                 |
+    /// | 8-11  | u32 sample rate                        |
+    /// | 12-15 | u32 samples per bucket                 |
+    /// | 16-19 | u32 bucket count                       |
+    /// | 20-   | f32 min, f32 max, f32 rms per bucket   |
+    pub fn finish(mut self) -> Vec<u8> {
+        if self.count > 0 {
+            self.flush();
+        }
+
+        let mut data = Vec::with_capacity(20 + self.peaks.len() * 12);
+        data.extend_from_slice(MAGIC);
+        data.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+        data.extend_from_slice(&PEAK_SAMPLE_RATE.to_le_bytes());
+        data.extend_from_slice(&(self.samples_per_bucket as u32).to_le_bytes());
+        data.extend_from_slice(&(self.peaks.len() as u32).to_le_bytes());
+        for peak in &self.peaks {
+            for value in peak {
+                data.extend_from_slice(&value.to_le_bytes());
+            }
+        }
+        data
+    }
+
+    fn flush(&mut self) {
+        let rms = (self.sum_squares / self.count as f64).sqrt() as f32;
+        self.peaks.push([self.min, self.max, rms]);
+        self.count = 0;
+        self.min = f32::MAX;
+        self.max = f32::MIN;
+        self.sum_squares = 0.0;
+    }
+}
+
+/// Check that cached data is in the current format
+pub fn is_valid(data: &[u8]) -> bool {
+    data.len() >= 20
+        && &data[0..4] == MAGIC
+        && data[4..8] == FORMAT_VERSION.to_le_bytes()
+        && {
+            let buckets = u32::from_le_bytes([data[16], data[17], data[18], data[19]]) as usize;
+            data.len() == 20 + buckets * 12
+        }
+}
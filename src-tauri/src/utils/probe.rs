@@ -0,0 +1,182 @@
+use serde::Serialize;
+use tauri::AppHandle;
+use crate::utils::ffmpeg::{self, FFmpegError};
+use crate::utils::interlace::InterlaceAnalysis;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct VideoStreamInfo {
+    pub codec: String,
+    pub width: u32,
+    pub height: u32,
+    pub fps: Option<f64>,
+    pub rotation: u32, // Clockwise display rotation from the display matrix (0, 90, 180 or 270)
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AudioStreamInfo {
+    pub codec: String,
+    pub sample_rate: Option<u32>,
+    pub channel_layout: Option<String>, // e.g. "mono", "stereo", "5.1(side)"
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct MediaInfo {
+    pub duration: Option<f64>, // Seconds
+    pub video: Option<VideoStreamInfo>, // First video stream
+    pub audio: Option<AudioStreamInfo>, // First audio stream
+    pub interlace: Option<InterlaceAnalysis>, // Only filled in when interlace analysis was requested
+}
+
+/// Probe a media file by parsing the stream summary FFmpeg prints for its input
+pub fn probe_media(app_handle: &AppHandle, input_path: &str) -> Result<MediaInfo, FFmpegError> {
+    let output = ffmpeg::read_ffmpeg_output(
+        app_handle,
+        vec![
+            "-hide_banner".to_string(),
+            "-i".to_string(),
+            input_path.to_string(),
+        ],
+    )?;
+
+    let info = parse_media_info(&output);
+    if info.duration.is_none() && info.video.is_none() && info.audio.is_none() {
+        return Err(FFmpegError {
+            message: "Failed to read media information".to_string(),
+        });
+    }
+
+    Ok(info)
+}
+
+/// Parse the input summary printed by `ffmpeg -i`
+pub fn parse_media_info(output: &str) -> MediaInfo {
+    let mut info = MediaInfo::default();
+    let mut in_first_video = false;
+
+    for line in output.lines() {
+        let line = line.trim();
+
+        if info.duration.is_none() {
+            if let Some(duration) = ffmpeg::parse_duration(line) {
+                info.duration = Some(duration);
+            }
+        }
+
+        if !line.starts_with("Stream #") {
+            // Metadata and side data lines belong to the preceding stream
+            if in_first_video {
+                if let (Some(video), Some(rotation)) = (info.video.as_mut(), parse_rotation(line)) {
+                    video.rotation = rotation;
+                }
+            }
+            continue;
+        }
+
+        in_first_video = false;
+        if let Some(start) = line.find(": Video: ") {
+            if info.video.is_none() {
+                info.video = parse_video_stream(&line[start + 9..]);
+                in_first_video = info.video.is_some();
+            }
+        } else if let Some(start) = line.find(": Audio: ") {
+            if info.audio.is_none() {
+                info.audio = Some(parse_audio_stream(&line[start + 9..]));
+            }
+        }
+    }
+
+    info
+}
+
+fn parse_video_stream(description: &str) -> Option<VideoStreamInfo> {
+    let parts = split_top_level(description);
+    let codec = parse_codec(parts.first()?);
+
+    let (width, height) = parts.iter().find_map(|part| {
+        let size = part.split_whitespace().next()?;
+        let (w, h) = size.split_once('x')?;
+        Some((w.parse().ok()?, h.parse().ok()?))
+    })?;
+
+    let fps = parts
+        .iter()
+        .find_map(|part| part.strip_suffix(" fps").and_then(|v| v.trim().parse().ok()));
+
+    Some(VideoStreamInfo {
+        codec,
+        width,
+        height,
+        fps,
+        rotation: 0,
+    })
+}
+
+/// Parse `displaymatrix: rotation of -90.00 degrees` (counter-clockwise)
+/// or the older `rotate : 90` stream tag (clockwise)
+fn parse_rotation(line: &str) -> Option<u32> {
+    let clockwise = if let Some(start) = line.find("rotation of ") {
+        let value = line[start + 12..].split_whitespace().next()?;
+        -value.parse::<f64>().ok()?
+    } else if line.starts_with("rotate") {
+        let (_, value) = line.split_once(':')?;
+        value.trim().parse::<f64>().ok()?
+    } else {
+        return None;
+    };
+
+    // Snap to the nearest quarter turn
+    let quarter_turns = (clockwise / 90.0).round() as i64;
+    Some((quarter_turns.rem_euclid(4) * 90) as u32)
+}
+
+fn parse_audio_stream(description: &str) -> AudioStreamInfo {
+    let parts = split_top_level(description);
+    let codec = parts.first().map(|p| parse_codec(p)).unwrap_or_default();
+
+    let sample_rate = parts
+        .iter()
+        .find_map(|part| part.strip_suffix(" Hz").and_then(|v| v.trim().parse().ok()));
+
+    // The channel layout directly follows the sample rate
+    let channel_layout = parts
+        .iter()
+        .position(|part| part.ends_with(" Hz"))
+        .and_then(|i| parts.get(i + 1))
+        .map(|layout| layout.to_string());
+
+    AudioStreamInfo {
+        codec,
+        sample_rate,
+        channel_layout,
+    }
+}
+
+fn parse_codec(part: &str) -> String {
+    part.split_whitespace().next().unwrap_or("unknown").to_string()
+}
+
+/// Split a stream description on commas that are not inside parentheses or brackets
+fn split_top_level(description: &str) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut current = String::new();
+    let mut depth = 0i32;
+
+    for c in description.chars() {
+        match c {
+            '(' | '[' => depth += 1,
+            ')' | ']' => depth -= 1,
+            ',' if depth == 0 => {
+                parts.push(current.trim().to_string());
+                current.clear();
+                continue;
+            }
+            _ => {}
+        }
+        current.push(c);
+    }
+    if !current.trim().is_empty() {
+        parts.push(current.trim().to_string());
+    }
+
+    parts
+}
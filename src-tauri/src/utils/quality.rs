@@ -0,0 +1,192 @@
+use serde::Serialize;
+use std::path::Path;
+use std::process::Child;
+use std::sync::{Arc, Mutex};
+use tauri::AppHandle;
+use crate::utils::ffmpeg::{self, FFmpegError};
+use crate::utils::filter_graph;
+
+/// PSNR of identical frames is infinite; report it as this instead so it can be averaged
+const MAX_PSNR: f64 = 100.0;
+
+/// Per-frame values of one metric and their summary
+#[derive(Debug, Clone, Serialize)]
+pub struct MetricSeries {
+    pub values: Vec<f64>,
+    pub mean: f64,
+    pub min: f64,
+    pub max: f64,
+    pub p5: f64, // 5th percentile, the level the worst frames fall to
+    pub harmonic_mean: f64, // Weighs bad frames more than the mean (as VMAF's pooling does)
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct QualityReport {
+    pub frames: usize,
+    pub width: u32, // Resolution both inputs were compared at
+    pub height: u32,
+    pub psnr: MetricSeries, // dB
+    pub ssim: MetricSeries, // 0-1
+    pub vmaf: Option<MetricSeries>, // 0-100, when FFmpeg has libvmaf
+}
+
+impl MetricSeries {
+    pub fn from_values(values: Vec<f64>) -> Option<Self> {
+        if values.is_empty() {
+            return None;
+        }
+        let count = values.len() as f64;
+        let mut sorted = values.clone();
+        sorted.sort_by(|a, b| a.total_cmp(b));
+        let p5_index = ((count * 0.05).floor() as usize).min(sorted.len() - 1);
+        let reciprocal_sum: f64 = values.iter().map(|value| 1.0 / (value + 1.0)).sum();
+
+        Some(MetricSeries {
+            mean: values.iter().sum::<f64>() / count,
+            min: sorted[0],
+            max: sorted[sorted.len() - 1],
+            p5: sorted[p5_index],
+            // Shifted by one like libvmaf, so zero scores don't divide by zero
+            harmonic_mean: count / reciprocal_sum - 1.0,
+            values,
+        })
+    }
+}
+
+/// One comparison; the input options go before each `-i` (e.g. `-ss`/`-t` to
+/// compare against part of the reference)
+pub struct QualityJob<'a> {
+    pub reference: &'a str,
+    pub reference_options: Vec<String>,
+    pub distorted: &'a str,
+    pub distorted_options: Vec<String>,
+    pub width: u32, // Both inputs are scaled to this size, usually the reference's
+    pub height: u32,
+    pub fps: Option<f64>, // Reference frame rate, the distorted input is resampled to it
+    pub vmaf: bool,
+    pub work_dir: &'a Path, // Receives the metric logs
+}
+
+/// Filtergraph scaling both inputs to `width`x`height`, aligning their timestamps
+/// (and frame rate, when known) and feeding them to the metric filters. Input 0 is
+/// the distorted file and input 1 the reference, as libvmaf expects. Each metric
+/// stops at the end of the shorter input instead of repeating its last frame.
+pub fn metrics_graph(width: u32, height: u32, fps: Option<f64>, work_dir: &Path, vmaf: bool) -> String {
+    let prepare = |label: &str, input: usize, fps: Option<f64>| {
+        let fps_filter = fps.map(|fps| format!("fps={},", fps)).unwrap_or_default();
+        format!(
+            "[{}:v]{}scale={}:{}:flags=bicubic,setsar=1,format=yuv420p,setpts=PTS-STARTPTS[{}]",
+            input, fps_filter, width, height, label
+        )
+    };
+    let stats_path = |name: &str| filter_graph::escape_filter_path(&work_dir.join(name).to_string_lossy());
+    let branches = if vmaf { 3 } else { 2 };
+
+    let mut graph = vec![
+        // The distorted file is resampled to the reference frame rate so frames pair up
+        prepare("dist", 0, fps),
+        prepare("ref", 1, None),
+        format!("[dist]split={n}[d0][d1]{}", if vmaf { "[d2]" } else { "" }, n = branches),
+        format!("[ref]split={n}[r0][r1]{}", if vmaf { "[r2]" } else { "" }, n = branches),
+        format!("[d0][r0]psnr=stats_file={}:shortest=1", stats_path("psnr.log")),
+        format!("[d1][r1]ssim=stats_file={}:shortest=1", stats_path("ssim.log")),
+    ];
+    if vmaf {
+        let threads = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(4);
+        graph.push(format!(
+            "[d2][r2]libvmaf=log_fmt=json:log_path={}:n_threads={}:shortest=1",
+            stats_path("vmaf.json"),
+            threads
+        ));
+    }
+    graph.join(";")
+}
+
+/// Compare two inputs frame by frame
+pub fn measure_quality(
+    app_handle: &AppHandle,
+    job: &QualityJob,
+    process_state: Arc<Mutex<Option<Child>>>,
+    duration: Option<f64>,
+    progress_range: (f64, f64),
+) -> Result<QualityReport, FFmpegError> {
+    let work_dir = job.work_dir;
+    std::fs::create_dir_all(work_dir).map_err(|e| FFmpegError {
+        message: format!("Failed to create temp directory: {}", e),
+    })?;
+
+    let mut args = vec!["-hide_banner".to_string()];
+    args.extend(job.distorted_options.iter().cloned());
+    args.push("-i".to_string());
+    args.push(job.distorted.to_string());
+    args.extend(job.reference_options.iter().cloned());
+    args.push("-i".to_string());
+    args.push(job.reference.to_string());
+    args.extend(vec![
+        "-filter_complex".to_string(),
+        metrics_graph(job.width, job.height, job.fps, work_dir, job.vmaf),
+        "-an".to_string(),
+        "-f".to_string(),
+        "null".to_string(),
+        "-".to_string(),
+    ]);
+
+    ffmpeg::execute_ffmpeg_pass(app_handle, args, "conversion-progress", process_state, duration, progress_range)?;
+
+    let read = |name: &str| {
+        std::fs::read_to_string(work_dir.join(name)).map_err(|e| FFmpegError {
+            message: format!("Failed to read {}: {}", name, e),
+        })
+    };
+    let missing = |metric: &str| FFmpegError {
+        message: format!("FFmpeg did not report any {} values", metric),
+    };
+
+    let psnr = MetricSeries::from_values(parse_stats(&read("psnr.log")?, "psnr_avg"))
+        .ok_or_else(|| missing("PSNR"))?;
+    let ssim = MetricSeries::from_values(parse_stats(&read("ssim.log")?, "All"))
+        .ok_or_else(|| missing("SSIM"))?;
+    let vmaf = if job.vmaf {
+        Some(MetricSeries::from_values(parse_vmaf_log(&read("vmaf.json")?)).ok_or_else(|| missing("VMAF"))?)
+    } else {
+        None
+    };
+
+    Ok(QualityReport {
+        frames: psnr.values.len(),
+        width: job.width,
+        height: job.height,
+        psnr,
+        ssim,
+        vmaf,
+    })
+}
+
+/// Read one `key:value` field per line from a `psnr` or `ssim` stats file
+pub fn parse_stats(log: &str, key: &str) -> Vec<f64> {
+    log.lines()
+        .filter_map(|line| {
+            line.split_whitespace()
+                .filter_map(|field| field.split_once(':'))
+                .find(|(name, _)| *name == key)
+                .and_then(|(_, value)| value.parse::<f64>().ok())
+        })
+        .map(|value| value.min(MAX_PSNR))
+        .collect()
+}
+
+/// Per-frame scores from a libvmaf JSON log
+pub fn parse_vmaf_log(log: &str) -> Vec<f64> {
+    let Ok(json) = serde_json::from_str::<serde_json::Value>(log) else {
+        return Vec::new();
+    };
+    json["frames"]
+        .as_array()
+        .map(|frames| {
+            frames
+                .iter()
+                .filter_map(|frame| frame["metrics"]["vmaf"].as_f64())
+                .collect()
+        })
+        .unwrap_or_default()
+}
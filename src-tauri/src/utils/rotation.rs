@@ -0,0 +1,106 @@
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+use crate::utils::ffmpeg;
+use crate::utils::filter_graph::FilterGraph;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RotationOptions {
+    pub rotate: Option<u32>, // Clockwise: 90, 180 or 270
+    pub flip: Option<String>, // "horizontal", "vertical" or "both"
+    pub mode: Option<String>, // "transpose" (default, re-encodes pixels) or "metadata" (stream copy)
+}
+
+impl RotationOptions {
+    /// Only rewrite the display matrix instead of transposing the pixels
+    pub fn is_metadata_mode(&self) -> bool {
+        self.mode.as_deref().is_some_and(|m| m.eq_ignore_ascii_case("metadata"))
+    }
+}
+
+/// Physically rotate the frames by the requested amount. FFmpeg already autorotates
+/// sources that only store their rotation as metadata, so only the extra turn is applied.
+pub fn apply_transpose(graph: &mut FilterGraph, options: Option<&RotationOptions>) -> Result<(), String> {
+    let rotate = requested_rotation(options)?;
+    let (hflip, vflip) = requested_flip(options)?;
+
+    match rotate {
+        90 => graph.video("transpose=clock"),
+        180 => {
+            graph.video("hflip");
+            graph.video("vflip");
+        }
+        270 => graph.video("transpose=cclock"),
+        _ => {}
+    }
+
+    if hflip {
+        graph.video("hflip");
+    }
+    if vflip {
+        graph.video("vflip");
+    }
+
+    Ok(())
+}
+
+/// The `-display_*` input options appeared in FFmpeg 7.0
+const DISPLAY_OPTIONS_VERSION: u32 = 7;
+
+/// Input options that replace the display matrix when stream copying
+pub fn metadata_input_args(
+    app_handle: &AppHandle,
+    source_rotation: u32,
+    options: &RotationOptions,
+) -> Result<Vec<String>, String> {
+    let rotate = requested_rotation(Some(options))?;
+    let (hflip, vflip) = requested_flip(Some(options))?;
+
+    if let Some(version) = ffmpeg::major_version(app_handle).map_err(|e| e.message)? {
+        if version < DISPLAY_OPTIONS_VERSION {
+            return Err(format!(
+                "Metadata rotation needs FFmpeg {} or newer (found {}). Use transpose mode instead",
+                DISPLAY_OPTIONS_VERSION, version
+            ));
+        }
+    }
+
+    // FFmpeg expects the display rotation counter-clockwise
+    let clockwise = (source_rotation + rotate) % 360;
+    let mut args = vec![
+        "-display_rotation:v:0".to_string(),
+        ((360 - clockwise) % 360).to_string(),
+    ];
+    if hflip {
+        args.push("-display_hflip:v:0".to_string());
+    }
+    if vflip {
+        args.push("-display_vflip:v:0".to_string());
+    }
+
+    Ok(args)
+}
+
+/// Whether the output format can carry a display matrix
+pub fn supports_rotation_metadata(format: &str) -> bool {
+    matches!(format.to_lowercase().as_str(), "mp4" | "mov" | "m4v")
+}
+
+fn requested_rotation(options: Option<&RotationOptions>) -> Result<u32, String> {
+    match options.and_then(|o| o.rotate).unwrap_or(0) {
+        r @ (0 | 90 | 180 | 270) => Ok(r),
+        other => Err(format!("Invalid rotation: {}. Use 90, 180 or 270", other)),
+    }
+}
+
+fn requested_flip(options: Option<&RotationOptions>) -> Result<(bool, bool), String> {
+    match options.and_then(|o| o.flip.as_deref()).map(|f| f.to_lowercase()) {
+        None => Ok((false, false)),
+        Some(flip) => match flip.as_str() {
+            "none" => Ok((false, false)),
+            "horizontal" => Ok((true, false)),
+            "vertical" => Ok((false, true)),
+            "both" => Ok((true, true)),
+            other => Err(format!("Invalid flip: {}. Use 'horizontal', 'vertical' or 'both'", other)),
+        },
+    }
+}
@@ -0,0 +1,150 @@
+use serde::Serialize;
+use std::process::Child;
+use std::sync::{Arc, Mutex};
+use tauri::AppHandle;
+use crate::utils::ffmpeg::{self, FFmpegError};
+
+/// Frames analysed by the `thumbnail` filter when picking a representative frame
+pub const THUMBNAIL_BATCH: u32 = 100;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SceneChange {
+    pub time: f64, // Seconds
+    pub score: f64, // 0.0 - 1.0, how different the frame is from the previous one
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Chapter {
+    pub start: f64,
+    pub end: f64,
+    pub title: String,
+}
+
+/// Filter chain that passes only frames whose scene score exceeds `threshold`
+/// and logs their timestamp and score
+pub fn scene_filter(threshold: f64) -> Result<String, String> {
+    if threshold <= 0.0 || threshold > 1.0 {
+        return Err("Scene threshold must be greater than 0 and at most 1".to_string());
+    }
+    Ok(format!(
+        "select='gt(scene,{})',metadata=print:key=lavfi.scene_score,showinfo",
+        threshold
+    ))
+}
+
+/// Run scene analysis over the whole input
+pub fn detect_scenes(
+    app_handle: &AppHandle,
+    input_path: &str,
+    threshold: f64,
+    process_state: Arc<Mutex<Option<Child>>>,
+    duration: Option<f64>,
+    progress_range: (f64, f64),
+) -> Result<Vec<SceneChange>, FFmpegError> {
+    let filter = scene_filter(threshold).map_err(|message| FFmpegError { message })?;
+    let args = vec![
+        "-hide_banner".to_string(),
+        "-i".to_string(),
+        input_path.to_string(),
+        "-an".to_string(),
+        "-sn".to_string(),
+        "-vf".to_string(),
+        filter,
+        "-f".to_string(),
+        "null".to_string(),
+        "-".to_string(),
+    ];
+
+    let log = ffmpeg::execute_ffmpeg_capture(
+        app_handle,
+        args,
+        "conversion-progress",
+        process_state,
+        duration,
+        progress_range,
+    )?;
+
+    Ok(parse_scene_output(&log))
+}
+
+/// Pair each `lavfi.scene_score=` line from `metadata` with the `pts_time:` of
+/// the `showinfo` line that follows it for the same frame
+pub fn parse_scene_output(log: &str) -> Vec<SceneChange> {
+    let mut scenes = Vec::new();
+    let mut pending_score: Option<f64> = None;
+
+    for line in log.lines() {
+        if let Some(pos) = line.find("lavfi.scene_score=") {
+            pending_score = line[pos + 18..].trim().parse().ok();
+        } else if line.contains("Parsed_showinfo") {
+            let time = line
+                .find("pts_time:")
+                .and_then(|pos| line[pos + 9..].split_whitespace().next())
+                .and_then(|value| value.parse::<f64>().ok());
+            if let Some(time) = time {
+                scenes.push(SceneChange {
+                    time,
+                    score: pending_score.take().unwrap_or(0.0),
+                });
+            }
+        }
+    }
+
+    scenes
+}
+
+/// Start times of each scene, including the opening one at 0.
+/// Cuts closer than `min_length` seconds to the previous one are dropped.
+pub fn scene_starts(scenes: &[SceneChange], min_length: f64) -> Vec<f64> {
+    let mut starts = vec![0.0];
+    for scene in scenes {
+        if scene.time - starts[starts.len() - 1] >= min_length {
+            starts.push(scene.time);
+        }
+    }
+    starts
+}
+
+/// Chapters spanning the whole file, one per scene
+pub fn scene_chapters(scenes: &[SceneChange], duration: f64, min_length: f64) -> Vec<Chapter> {
+    let starts: Vec<f64> = scene_starts(scenes, min_length)
+        .into_iter()
+        .filter(|start| *start < duration)
+        .collect();
+
+    starts
+        .iter()
+        .enumerate()
+        .map(|(index, start)| Chapter {
+            start: *start,
+            end: starts.get(index + 1).copied().unwrap_or(duration),
+            title: format!("Scene {}", index + 1),
+        })
+        .collect()
+}
+
+/// FFMETADATA document with the chapters, for `-map_chapters`
+pub fn chapters_metadata(chapters: &[Chapter]) -> String {
+    let mut metadata = String::from(";FFMETADATA1\n");
+    for chapter in chapters {
+        metadata.push_str(&format!(
+            "[CHAPTER]\nTIMEBASE=1/1000\nSTART={}\nEND={}\ntitle={}\n",
+            (chapter.start * 1000.0).round() as u64,
+            (chapter.end * 1000.0).round() as u64,
+            escape_metadata(&chapter.title),
+        ));
+    }
+    metadata
+}
+
+/// `=`, `;`, `#`, `\` and newlines are special in FFMETADATA values
+fn escape_metadata(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, '=' | ';' | '#' | '\\' | '\n') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
@@ -0,0 +1,112 @@
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct TimeSpan {
+    pub start: f64, // Seconds
+    pub end: f64,
+}
+
+impl TimeSpan {
+    pub fn duration(&self) -> f64 {
+        self.end - self.start
+    }
+}
+
+/// `silencedetect` filter for the given noise floor (dB) and minimum silence length (seconds)
+pub fn silencedetect_filter(noise_db: f64, min_duration: f64) -> Result<String, String> {
+    if !(-90.0..=0.0).contains(&noise_db) {
+        return Err("Noise floor must be between -90 and 0 dB".to_string());
+    }
+    if min_duration <= 0.0 {
+        return Err("Minimum silence duration must be greater than 0".to_string());
+    }
+    Ok(format!("silencedetect=n={}dB:d={}", noise_db, min_duration))
+}
+
+/// Parse `silence_start: 12.3` / `silence_end: 15.6 | silence_duration: 3.3` lines.
+/// Silence that runs to the end of the file is closed at `duration`.
+pub fn parse_silence_output(log: &str, duration: Option<f64>) -> Vec<TimeSpan> {
+    let mut ranges = Vec::new();
+    let mut open_start: Option<f64> = None;
+
+    for line in log.lines() {
+        if let Some(start) = parse_value_after(line, "silence_start:") {
+            open_start = Some(start.max(0.0));
+        } else if let Some(end) = parse_value_after(line, "silence_end:") {
+            if let Some(start) = open_start.take() {
+                ranges.push(TimeSpan { start, end });
+            }
+        }
+    }
+
+    if let (Some(start), Some(end)) = (open_start, duration) {
+        if end > start {
+            ranges.push(TimeSpan { start, end });
+        }
+    }
+
+    ranges
+}
+
+/// Turn silent ranges into the ranges to keep, widening each kept segment by
+/// `padding` seconds on both sides so speech isn't clipped
+pub fn keep_ranges(silences: &[TimeSpan], duration: f64, padding: f64) -> Vec<TimeSpan> {
+    let mut keep = Vec::new();
+    let mut cursor = 0.0;
+
+    for silence in silences {
+        if silence.start > cursor {
+            keep.push(TimeSpan { start: cursor, end: silence.start });
+        }
+        cursor = cursor.max(silence.end);
+    }
+    if cursor < duration {
+        keep.push(TimeSpan { start: cursor, end: duration });
+    }
+
+    // Pad and merge segments that now overlap
+    let mut merged: Vec<TimeSpan> = Vec::new();
+    for span in keep {
+        let padded = TimeSpan {
+            start: (span.start - padding).max(0.0),
+            end: (span.end + padding).min(duration),
+        };
+        match merged.last_mut() {
+            Some(last) if padded.start <= last.end => last.end = last.end.max(padded.end),
+            _ => merged.push(padded),
+        }
+    }
+
+    merged
+}
+
+/// Filtergraph cutting each span out with `trim`/`atrim` and joining them with `concat`
+/// into `[v]` (when `has_video`) and `[a]`. Every segment keeps its own timestamps, so
+/// variable frame rate sources stay in sync. Long recordings produce many segments,
+/// so the graph is meant for `-filter_complex_script` rather than the command line.
+pub fn concat_graph(spans: &[TimeSpan], has_video: bool) -> String {
+    let mut graph = Vec::new();
+    let mut concat_inputs = String::new();
+    for (i, span) in spans.iter().enumerate() {
+        let range = format!("start={:.3}:end={:.3}", span.start, span.end);
+        if has_video {
+            graph.push(format!("[0:v]trim={},setpts=PTS-STARTPTS[v{}]", range, i));
+            concat_inputs.push_str(&format!("[v{}]", i));
+        }
+        graph.push(format!("[0:a]atrim={},asetpts=PTS-STARTPTS[a{}]", range, i));
+        concat_inputs.push_str(&format!("[a{}]", i));
+    }
+    graph.push(format!(
+        "{}concat=n={}:v={}:a=1{}[a]",
+        concat_inputs,
+        spans.len(),
+        u8::from(has_video),
+        if has_video { "[v]" } else { "" }
+    ));
+    graph.join(";\n")
+}
+
+fn parse_value_after(line: &str, label: &str) -> Option<f64> {
+    let start = line.find(label)? + label.len();
+    line[start..].split_whitespace().next()?.parse().ok()
+}
@@ -0,0 +1,105 @@
+use crate::utils::filter_graph::FilterGraph;
+
+pub const MIN_SPEED: f64 = 0.25;
+pub const MAX_SPEED: f64 = 4.0;
+
+/// Reverse keeps every decoded frame in memory, so only short clips are allowed
+pub const MAX_REVERSE_DURATION: f64 = 30.0;
+
+/// Add a speed change to the graph: `setpts` for video and chained `atempo`
+/// (or `asetrate` when pitch should follow the speed) for audio
+pub fn apply_speed(
+    graph: &mut FilterGraph,
+    factor: f64,
+    preserve_pitch: bool,
+    sample_rate: Option<u32>,
+    has_audio: bool,
+) -> Result<(), String> {
+    if !(MIN_SPEED..=MAX_SPEED).contains(&factor) {
+        return Err(format!("Speed must be between {}x and {}x", MIN_SPEED, MAX_SPEED));
+    }
+    if (factor - 1.0).abs() < f64::EPSILON {
+        return Ok(());
+    }
+
+    graph.video(format!("setpts=PTS/{}", factor));
+
+    if has_audio {
+        if preserve_pitch {
+            for tempo in atempo_chain(factor) {
+                graph.audio(format!("atempo={}", tempo));
+            }
+        } else {
+            // Resample like a tape machine: pitch moves with the speed
+            let rate = sample_rate.unwrap_or(48000);
+            graph.audio(format!("asetrate={}", (rate as f64 * factor).round() as u32));
+            graph.audio(format!("aresample={}", rate));
+        }
+    }
+
+    Ok(())
+}
+
+/// Split a tempo factor into `atempo` steps that each stay within 0.5-2.0
+fn atempo_chain(factor: f64) -> Vec<f64> {
+    let mut remaining = factor;
+    let mut chain = Vec::new();
+
+    while remaining > 2.0 {
+        chain.push(2.0);
+        remaining /= 2.0;
+    }
+    while remaining < 0.5 {
+        chain.push(0.5);
+        remaining /= 0.5;
+    }
+    chain.push(remaining);
+
+    chain
+}
+
+/// Add reverse or boomerang (forward followed by reverse) playback to the graph
+pub fn apply_playback_direction(
+    graph: &mut FilterGraph,
+    mode: &str,
+    duration: Option<f64>,
+    has_audio: bool,
+) -> Result<(), String> {
+    let mode = mode.to_lowercase();
+    if mode == "forward" {
+        return Ok(());
+    }
+
+    match duration {
+        Some(duration) if duration <= MAX_REVERSE_DURATION => {}
+        Some(_) => {
+            return Err(format!(
+                "Reverse and boomerang are limited to clips of {} seconds or less",
+                MAX_REVERSE_DURATION
+            ));
+        }
+        None => return Err("Could not determine video duration for reverse playback".to_string()),
+    }
+
+    match mode.as_str() {
+        "reverse" => {
+            graph.video("reverse");
+            if has_audio {
+                graph.audio("areverse");
+            }
+        }
+        "boomerang" => {
+            graph.video_graph(
+                "[{in}]split[boomfwd][boomrev];[boomrev]reverse[boomrevout];[boomfwd][boomrevout]concat=n=2:v=1:a=0[{out}]",
+            );
+            if has_audio {
+                graph.audio_graph(
+                    "[{in}]asplit[aboomfwd][aboomrev];[aboomrev]areverse[aboomrevout];[aboomfwd][aboomrevout]concat=n=2:v=0:a=1[{out}]",
+                );
+            }
+        }
+        other => return Err(format!("Invalid playback mode: {}. Use 'forward', 'reverse' or 'boomerang'", other)),
+    }
+
+    Ok(())
+}
@@ -0,0 +1,94 @@
+use serde::{Deserialize, Serialize};
+use crate::utils::cache::SourceStamp;
+
+/// Layout of the frames in the sprite sheets
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SpriteLayout {
+    pub interval: f64, // Seconds between frames
+    pub tile_width: u32,
+    pub tile_height: u32,
+    pub columns: u32,
+    pub rows: u32,
+}
+
+/// Stored next to the sprites so stale ones can be detected
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SpriteManifest {
+    pub source: SourceStamp,
+    pub layout: SpriteLayout,
+    pub sprite_files: Vec<String>,
+    pub vtt_file: String,
+}
+
+impl SpriteLayout {
+    pub fn frames_per_sheet(&self) -> u32 {
+        self.columns * self.rows
+    }
+
+    /// Number of frames taken from a file of the given duration
+    pub fn frame_count(&self, duration: f64) -> u32 {
+        ((duration / self.interval).ceil() as u32).max(1)
+    }
+
+    pub fn sheet_count(&self, duration: f64) -> u32 {
+        self.frame_count(duration).div_ceil(self.frames_per_sheet())
+    }
+
+    /// Filter chain producing the sheets, one image per `columns`x`rows` frames
+    pub fn filter(&self) -> String {
+        format!(
+            "fps=1/{},scale={}:{},tile={}x{}",
+            self.interval, self.tile_width, self.tile_height, self.columns, self.rows
+        )
+    }
+}
+
+/// Tile height matching `tile_width` for a picture of the given (displayed) size, rounded to an even number
+pub fn tile_height(tile_width: u32, width: u32, height: u32) -> u32 {
+    let scaled = tile_width as f64 * height as f64 / width as f64;
+    (((scaled / 2.0).round() as u32) * 2).max(2)
+}
+
+/// File name of the n-th sheet (1-based, matching the `%03d` output pattern)
+pub fn sheet_file_name(index: u32) -> String {
+    format!("sprite_{:03}.jpg", index)
+}
+
+/// WebVTT track mapping each interval to its region in the sheets
+pub fn build_vtt(layout: &SpriteLayout, duration: f64) -> String {
+    let mut vtt = String::from("WEBVTT\n");
+    let per_sheet = layout.frames_per_sheet();
+
+    for frame in 0..layout.frame_count(duration) {
+        let start = frame as f64 * layout.interval;
+        let end = ((frame + 1) as f64 * layout.interval).min(duration);
+        let position = frame % per_sheet;
+        let x = (position % layout.columns) * layout.tile_width;
+        let y = (position / layout.columns) * layout.tile_height;
+
+        vtt.push_str(&format!(
+            "\n{} --> {}\n{}#xywh={},{},{},{}\n",
+            vtt_timestamp(start),
+            vtt_timestamp(end),
+            sheet_file_name(frame / per_sheet + 1),
+            x,
+            y,
+            layout.tile_width,
+            layout.tile_height
+        ));
+    }
+
+    vtt
+}
+
+/// `HH:MM:SS.mmm`
+fn vtt_timestamp(seconds: f64) -> String {
+    let millis = (seconds * 1000.0).round() as u64;
+    format!(
+        "{:02}:{:02}:{:02}.{:03}",
+        millis / 3_600_000,
+        (millis % 3_600_000) / 60_000,
+        (millis % 60_000) / 1000,
+        millis % 1000
+    )
+}
@@ -0,0 +1,27 @@
+use crate::utils::filter_graph::FilterGraph;
+
+/// Add a picture noise reduction filter to the graph.
+///
+/// `hqdn3d` is fast enough for long footage; `nlmeans` is much slower but
+/// keeps more detail in low-light shots.
+pub fn apply_video_denoise(graph: &mut FilterGraph, mode: &str, strength: Option<&str>) -> Result<(), String> {
+    let strength = strength.unwrap_or("medium").to_lowercase();
+
+    let filter = match (mode.to_lowercase().as_str(), strength.as_str()) {
+        ("hqdn3d", "light") => "hqdn3d=2:1.5:3:2.25",
+        ("hqdn3d", "medium") => "hqdn3d=4:3:6:4.5",
+        ("hqdn3d", "strong") => "hqdn3d=8:6:12:9",
+        ("nlmeans", "light") => "nlmeans=s=2:p=7:r=15",
+        ("nlmeans", "medium") => "nlmeans=s=4:p=7:r=15",
+        ("nlmeans", "strong") => "nlmeans=s=7:p=7:r=15",
+        ("hqdn3d" | "nlmeans", other) => {
+            return Err(format!("Invalid denoise strength: {}. Use 'light', 'medium' or 'strong'", other));
+        }
+        (other, _) => {
+            return Err(format!("Invalid video denoise mode: {}. Use 'hqdn3d' or 'nlmeans'", other));
+        }
+    };
+
+    graph.video(filter);
+    Ok(())
+}
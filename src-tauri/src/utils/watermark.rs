@@ -0,0 +1,194 @@
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use crate::utils::filter_graph::{self, FilterGraph};
+
+const DEFAULT_MARGIN: u32 = 24;
+const DEFAULT_IMAGE_SCALE: f64 = 0.15; // Fraction of the video width
+const DEFAULT_FONT_SIZE: u32 = 32;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimeRange {
+    pub start: f64,
+    pub end: Option<f64>, // Visible until the end of the video when omitted
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImageWatermark {
+    pub path: String, // PNG with alpha channel
+    pub position: Option<String>, // "top-left", "top-right", "bottom-left", "bottom-right" or "center"
+    pub margin: Option<u32>, // Distance from the edges in pixels
+    pub scale: Option<f64>, // Watermark width relative to video width (0.0-1.0)
+    pub opacity: Option<f64>, // 0.0 (invisible) to 1.0 (opaque)
+    #[serde(default)]
+    pub ranges: Vec<TimeRange>, // Always visible when empty
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TextWatermark {
+    pub text: String,
+    pub font_file: Option<String>,
+    pub font_size: Option<u32>,
+    pub font_color: Option<String>, // FFmpeg colour, e.g. "white" or "white@0.8"
+    #[serde(default)]
+    pub box_enabled: bool, // Draw a background box behind the text
+    pub box_color: Option<String>,
+    pub position: Option<String>, // Same values as for image watermarks
+    pub margin: Option<u32>,
+    #[serde(default)]
+    pub ranges: Vec<TimeRange>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WatermarkOptions {
+    pub image: Option<ImageWatermark>,
+    pub text: Option<TextWatermark>,
+}
+
+/// Add the image and/or text watermark to the video chain of the filter graph
+pub fn apply_watermark(graph: &mut FilterGraph, options: &WatermarkOptions) -> Result<(), String> {
+    if let Some(image) = &options.image {
+        apply_image_watermark(graph, image)?;
+    }
+    if let Some(text) = &options.text {
+        apply_text_watermark(graph, text)?;
+    }
+    Ok(())
+}
+
+fn apply_image_watermark(graph: &mut FilterGraph, image: &ImageWatermark) -> Result<(), String> {
+    if !PathBuf::from(&image.path).exists() {
+        return Err(format!("Watermark image does not exist: {}", image.path));
+    }
+
+    let scale = image.scale.unwrap_or(DEFAULT_IMAGE_SCALE);
+    if scale <= 0.0 || scale > 1.0 {
+        return Err("Watermark scale must be between 0 and 1".to_string());
+    }
+    let opacity = image.opacity.unwrap_or(1.0).clamp(0.0, 1.0);
+    let margin = image.margin.unwrap_or(DEFAULT_MARGIN);
+    let (x, y) = overlay_position(image.position.as_deref(), margin)?;
+
+    let index = graph.add_input(&image.path);
+    let source = format!("wmsrc{}", index);
+    let scaled = format!("wm{}", index);
+    let base = format!("wmbase{}", index);
+
+    graph.add_source(format!(
+        "[{}:v]format=rgba,colorchannelmixer=aa={}[{}]",
+        index, opacity, source
+    ));
+
+    let mut overlay = format!("overlay=x={}:y={}", x, y);
+    if let Some(enable) = enable_for(&image.ranges)? {
+        overlay.push_str(&format!(":enable={}", enable));
+    }
+
+    graph.video_graph(format!(
+        "[{source}][{{in}}]scale2ref=w=main_w*{scale}:h=ow/a[{scaled}][{base}];[{base}][{scaled}]{overlay}[{{out}}]",
+        source = source,
+        scale = scale,
+        scaled = scaled,
+        base = base,
+        overlay = overlay,
+    ));
+
+    Ok(())
+}
+
+fn apply_text_watermark(graph: &mut FilterGraph, text: &TextWatermark) -> Result<(), String> {
+    if text.text.trim().is_empty() {
+        return Err("Watermark text is empty".to_string());
+    }
+
+    let margin = text.margin.unwrap_or(DEFAULT_MARGIN);
+    let (x, y) = text_position(text.position.as_deref(), margin)?;
+
+    let mut filter = String::from("drawtext=");
+    if let Some(font_file) = text.font_file.clone().or_else(default_font_file) {
+        if !PathBuf::from(&font_file).exists() {
+            return Err(format!("Font file does not exist: {}", font_file));
+        }
+        filter.push_str(&format!("fontfile={}:", filter_graph::escape_filter_path(&font_file)));
+    }
+    filter.push_str(&format!(
+        "text={}:fontsize={}:fontcolor={}:x={}:y={}",
+        filter_graph::escape_drawtext_text(&text.text),
+        text.font_size.unwrap_or(DEFAULT_FONT_SIZE),
+        filter_graph::escape_filter_value(text.font_color.as_deref().unwrap_or("white")),
+        x,
+        y,
+    ));
+    if text.box_enabled {
+        filter.push_str(&format!(
+            ":box=1:boxcolor={}:boxborderw=10",
+            filter_graph::escape_filter_value(text.box_color.as_deref().unwrap_or("black@0.5"))
+        ));
+    }
+    if let Some(enable) = enable_for(&text.ranges)? {
+        filter.push_str(&format!(":enable={}", enable));
+    }
+
+    graph.video(filter);
+    Ok(())
+}
+
+/// Overlay coordinates (W/H = video size, w/h = watermark size)
+fn overlay_position(position: Option<&str>, margin: u32) -> Result<(String, String), String> {
+    let m = margin;
+    match position.unwrap_or("bottom-right").to_lowercase().as_str() {
+        "top-left" => Ok((format!("{}", m), format!("{}", m))),
+        "top-right" => Ok((format!("W-w-{}", m), format!("{}", m))),
+        "bottom-left" => Ok((format!("{}", m), format!("H-h-{}", m))),
+        "bottom-right" => Ok((format!("W-w-{}", m), format!("H-h-{}", m))),
+        "center" => Ok(("(W-w)/2".to_string(), "(H-h)/2".to_string())),
+        other => Err(format!("Invalid watermark position: {}", other)),
+    }
+}
+
+/// drawtext coordinates (w/h = video size, tw/th = text size)
+fn text_position(position: Option<&str>, margin: u32) -> Result<(String, String), String> {
+    let m = margin;
+    match position.unwrap_or("bottom-right").to_lowercase().as_str() {
+        "top-left" => Ok((format!("{}", m), format!("{}", m))),
+        "top-right" => Ok((format!("w-tw-{}", m), format!("{}", m))),
+        "bottom-left" => Ok((format!("{}", m), format!("h-th-{}", m))),
+        "bottom-right" => Ok((format!("w-tw-{}", m), format!("h-th-{}", m))),
+        "center" => Ok(("(w-tw)/2".to_string(), "(h-th)/2".to_string())),
+        other => Err(format!("Invalid watermark position: {}", other)),
+    }
+}
+
+fn enable_for(ranges: &[TimeRange]) -> Result<Option<String>, String> {
+    for range in ranges {
+        if range.start < 0.0 || range.end.is_some_and(|end| end <= range.start) {
+            return Err("Invalid watermark time range".to_string());
+        }
+    }
+    let ranges: Vec<(f64, Option<f64>)> = ranges.iter().map(|r| (r.start, r.end)).collect();
+    Ok(filter_graph::enable_expression(&ranges))
+}
+
+/// drawtext needs an explicit font file where fontconfig is not available
+pub fn default_font_file() -> Option<String> {
+    let candidate = if cfg!(target_os = "windows") {
+        "C:/Windows/Fonts/arial.ttf"
+    } else if cfg!(target_os = "macos") {
+        "/System/Library/Fonts/Helvetica.ttc"
+    } else {
+        return None;
+    };
+
+    if PathBuf::from(candidate).exists() {
+        Some(candidate.to_string())
+    } else {
+        None
+    }
+}
+
+/// `fontfile=` option (with its trailing separator) for drawtext, empty when the default font is used
+pub fn default_font_option() -> String {
+    match default_font_file() {
+        Some(font_file) => format!("fontfile={}:", filter_graph::escape_filter_path(&font_file)),
+        None => String::new(),
+    }
+}
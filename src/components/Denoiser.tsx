@@ -2,6 +2,7 @@ import { invoke } from "@tauri-apps/api/core";
 import VideoSelector from "./VideoSelector";
 import VideoList from "./VideoList";
 import VideoPlayer from "./VideoPlayer";
+import { ConvertResult } from "../hooks/useConversion";
 
 interface VideoInfo {
   path: string;
@@ -76,14 +77,14 @@ export default function Denoiser({
 
     try {
       const denoisedPath = getDenoisedPath(selectedVideo.path);
-      const result = await invoke<string>("denoise_video", {
+      const result = await invoke<ConvertResult>("denoise_video", {
         options: {
           input_path: selectedVideo.path,
           output_path: denoisedPath,
         },
       });
 
-      onDenoisedPathChange(result);
+      onDenoisedPathChange(result.output_path);
       onStatusChange("completed");
       onProgressChange(100);
       // Reset progress after a short delay
@@ -1,5 +1,6 @@
 import { useState, useEffect } from "react";
 import { invoke } from "@tauri-apps/api/core";
+import { MediaFile, isImageFile, mediaFileUrl } from "../utils/mediaFile";
 
 interface PreviewDisplayProps {
   previewPath: string;
@@ -11,6 +12,7 @@ export default function PreviewDisplay({
   previewType,
 }: PreviewDisplayProps) {
   const [previewUrl, setPreviewUrl] = useState<string>("");
+  const [isImage, setIsImage] = useState(previewType === "thumbnail");
   const [hasError, setHasError] = useState(false);
   const [errorMessage, setErrorMessage] = useState<string>("");
   const [isLoading, setIsLoading] = useState(true);
@@ -24,12 +26,14 @@ export default function PreviewDisplay({
       setPreviewUrl("");
       
       try {
-        // Use Tauri command to read file and get data URL
-        const dataUrl = await invoke<string>("read_preview_file", {
+        // Streamed through the media protocol; the reported type decides between
+        // an image (thumbnail, contact sheet, gif, ...) and a video (clip, highlights)
+        const preview = await invoke<MediaFile>("read_preview_file", {
           filePath: previewPath,
         });
-        
-        setPreviewUrl(dataUrl);
+
+        setIsImage(isImageFile(preview));
+        setPreviewUrl(mediaFileUrl(preview));
         setHasError(false);
         setErrorMessage("");
       } catch (error) {
@@ -70,7 +74,7 @@ export default function PreviewDisplay({
             <p className="text-xs text-vscode-text-secondary">Loading preview...</p>
           </div>
         ) : previewUrl ? (
-          previewType === "thumbnail" ? (
+          isImage ? (
             <img
               src={previewUrl}
               alt="Video thumbnail preview"
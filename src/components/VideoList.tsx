@@ -1,13 +1,12 @@
 import { useState, useEffect } from "react";
 import { invoke } from "@tauri-apps/api/core";
 import { useSettings } from "../hooks/useSettings";
+import { VideoInfo } from "../hooks/useConversion";
 import { getPreviewPath } from "../utils/pathUtils";
+import { MediaFile, mediaFileUrl } from "../utils/mediaFile";
 
-interface VideoInfo {
-  path: string;
-  name: string;
-  size: number;
-}
+// The list shows a 64px tile, so ask for a small variant (2x for high-DPI screens)
+const THUMBNAIL_WIDTH = 128;
 
 interface VideoListProps {
   video: VideoInfo | null;
@@ -37,10 +36,11 @@ export default function VideoList({ video, onClear }: VideoListProps) {
         
         // First, try to read existing thumbnail
         try {
-          const dataUrl = await invoke<string>("read_preview_file", {
+          const thumbnail = await invoke<MediaFile>("read_preview_file", {
             filePath: thumbnailPath,
+            maxWidth: THUMBNAIL_WIDTH,
           });
-          setThumbnailUrl(dataUrl);
+          setThumbnailUrl(mediaFileUrl(thumbnail));
           setThumbnailError(false);
           setIsLoadingThumbnail(false);
           return; // Successfully loaded existing thumbnail
@@ -51,7 +51,7 @@ export default function VideoList({ video, onClear }: VideoListProps) {
 
         // Generate thumbnail if it doesn't exist
         try {
-          await invoke<string>("generate_preview", {
+          await invoke("generate_preview", {
             options: {
               input_path: video.path,
               output_path: thumbnailPath,
@@ -61,10 +61,11 @@ export default function VideoList({ video, onClear }: VideoListProps) {
           });
 
           // Read the newly generated thumbnail
-          const dataUrl = await invoke<string>("read_preview_file", {
+          const thumbnail = await invoke<MediaFile>("read_preview_file", {
             filePath: thumbnailPath,
+            maxWidth: THUMBNAIL_WIDTH,
           });
-          setThumbnailUrl(dataUrl);
+          setThumbnailUrl(mediaFileUrl(thumbnail));
           setThumbnailError(false);
         } catch (error) {
           console.error("Failed to generate or read thumbnail:", error);
@@ -157,6 +158,11 @@ export default function VideoList({ video, onClear }: VideoListProps) {
               <p className="text-xs text-vscode-text-secondary">
                 {formatFileSize(video.size)}
               </p>
+              {video.warning && (
+                <p className="text-xs text-yellow-400 truncate" title={video.warning}>
+                  {video.warning}
+                </p>
+              )}
             </div>
           </div>
         </div>
@@ -1,6 +1,7 @@
 import { useState, useEffect, useRef } from "react";
-import { convertFileSrc } from "@tauri-apps/api/core";
 import { invoke } from "@tauri-apps/api/core";
+import { MediaFile, mediaFileUrl } from "../utils/mediaFile";
+import { TranscodePlayer, needsTranscode } from "../utils/transcodePlayer";
 
 interface VideoPlayerProps {
   videoPath: string;
@@ -11,33 +12,10 @@ export default function VideoPlayer({ videoPath }: VideoPlayerProps) {
   const [hasError, setHasError] = useState(false);
   const [errorMessage, setErrorMessage] = useState<string>("");
   const [isLoading, setIsLoading] = useState(true);
+  const [isTranscoding, setIsTranscoding] = useState(false);
   const videoContainerRef = useRef<HTMLDivElement>(null);
   const videoRef = useRef<HTMLVideoElement>(null);
 
-  // Check if format is browser-compatible
-  const isBrowserCompatible = (path: string): boolean => {
-    const ext = path.toLowerCase().split('.').pop() || '';
-    // Only MP4, WebM, and OGG are natively supported by browsers
-    // If no extension, we'll still try to load it (might be MP4 without extension)
-    if (!ext) {
-      return true; // Allow files without extensions - let browser decide
-    }
-    return ext === 'mp4' || ext === 'webm' || ext === 'ogg';
-  };
-
-  const getFormatMessage = (path: string): string => {
-    const ext = path.toLowerCase().split('.').pop() || '';
-    const formatName = ext.toUpperCase() || 'UNKNOWN';
-    
-    if (ext === 'avi' || ext === 'mov' || ext === 'mkv' || ext === 'flv' || ext === 'wmv' || ext === 'm4v') {
-      return `${formatName} format is not supported by web browsers. Only MP4, WebM, and OGG formats can be played in browsers. Please use the "Open in External Player" button to play this file, or convert to MP4 format for browser playback.`;
-    }
-    if (!ext) {
-      return `Video file has no extension. The file may be an MP4 but the browser cannot determine the format. Please ensure the file has a .mp4 extension, or use the "Open in External Player" button.`;
-    }
-    return `Video format (${formatName}) is not supported by browser. Only MP4, WebM, and OGG formats can be played in browsers. Please convert to MP4 format for browser playback, or use the "Open in External Player" button.`;
-  };
-
   useEffect(() => {
     // Convert file path to a URL that can be displayed
     const loadVideo = async () => {
@@ -45,86 +23,34 @@ export default function VideoPlayer({ videoPath }: VideoPlayerProps) {
       setHasError(false);
       setErrorMessage("");
       setVideoUrl("");
-      
-      // Check if format is browser-compatible before attempting to load
-      // But be lenient - allow files without extensions
-      const ext = videoPath.toLowerCase().split('.').pop() || '';
-      const knownIncompatible = ['avi', 'mov', 'mkv', 'flv', 'wmv', 'm4v'];
-      
-      if (ext && knownIncompatible.includes(ext)) {
-        setHasError(true);
-        setErrorMessage(getFormatMessage(videoPath));
-        setIsLoading(false);
-        return;
-      }
+      setIsTranscoding(false);
 
       try {
-        // First, try to read the file as a data URL (works for files < 100MB)
-        // This allows playing any video format in the browser
-        try {
-          const dataUrl = await invoke<string>("read_video_file", {
-            filePath: videoPath,
-          });
-          
-          console.log("Video loaded as data URL, size:", dataUrl.length, "chars");
-          setVideoUrl(dataUrl);
-          setHasError(false);
-          setErrorMessage("");
-          return;
-        } catch (dataUrlError) {
-          const errorMsg = dataUrlError instanceof Error ? dataUrlError.message : String(dataUrlError);
-          
-          // If file is too large, fall back to convertFileSrc
-          if (errorMsg.includes("too large")) {
-            console.log("File too large for data URL, trying convertFileSrc:", errorMsg);
-            // Continue to fallback below
-          } else {
-            // Other error - try convertFileSrc as fallback
-            console.log("Data URL failed, trying convertFileSrc:", errorMsg);
-          }
-        }
-
-        // Fallback: Try convertFileSrc for smaller files or as alternative
-        const verifiedPath = await invoke<string>("get_video_url", {
+        // Stream the file through the ripley:// protocol, which serves byte ranges so
+        // the player can seek through large files without loading them into memory
+        const file = await invoke<MediaFile>("read_video_file", {
           filePath: videoPath,
         });
 
-        let url: string | null = null;
-        const pathVariants = [
-          verifiedPath,
-          verifiedPath.replace(/^\/?([A-Za-z]:)/, "/$1"),
-          verifiedPath.replace(/^\/+/, ""),
-          videoPath.replace(/\\/g, "/"),
-        ];
-
-        for (const pathVariant of pathVariants) {
-          try {
-            const testUrl = convertFileSrc(pathVariant);
-            if (testUrl && (testUrl.startsWith("asset://") || testUrl.startsWith("http://") || testUrl.startsWith("https://"))) {
-              url = testUrl;
-              console.log("Successfully generated URL with convertFileSrc:", pathVariant);
-              break;
-            }
-          } catch (e) {
-            continue;
-          }
+        // Containers and codecs the webview can't play (MKV, AVI, HEVC, ...) are
+        // transcoded on the fly instead of being rejected
+        const media = await invoke<{ video: { codec: string } | null }>("probe_video", {
+          filePath: videoPath,
+        }).catch(() => null);
+        if (needsTranscode(file.mime_type, media?.video?.codec)) {
+          setIsTranscoding(true);
+          return;
         }
 
-        if (!url) {
-          url = convertFileSrc(videoPath.replace(/\\/g, "/"));
-        }
-        
+        const url = mediaFileUrl(file);
+
         console.log("Video loading details:", {
           originalPath: videoPath,
-          verifiedPath: verifiedPath,
+          verifiedPath: file.path,
+          mimeType: file.mime_type,
           generatedUrl: url,
-          urlProtocol: url?.substring(0, url.indexOf(':') + 1) || 'unknown',
         });
-        
-        if (!url || url.length === 0) {
-          throw new Error("Failed to generate video URL. The file might be too large (>100MB). Please use 'Open in External Player'.");
-        }
-        
+
         setVideoUrl(url);
         setHasError(false);
         setErrorMessage("");
@@ -135,8 +61,6 @@ export default function VideoPlayer({ videoPath }: VideoPlayerProps) {
         
         if (errorMsg.includes("does not exist") || errorMsg.includes("File does not exist")) {
           setErrorMessage("Video file not found. The file may have been moved or deleted.");
-        } else if (errorMsg.includes("too large")) {
-          setErrorMessage("Video file is too large (>100MB) to load in browser. Please use the 'Open in External Player' button to play the video.");
         } else if (errorMsg.includes("ERR_CONNECTION_REFUSED") || errorMsg.includes("connection")) {
           setErrorMessage("Cannot access video file. Please use the 'Open in External Player' button to play the video.");
         } else {
@@ -154,6 +78,22 @@ export default function VideoPlayer({ videoPath }: VideoPlayerProps) {
     }
   }, [videoPath]);
 
+  // Drive the live transcode while a non-native file is shown, and stop it
+  // (killing FFmpeg) when the player closes or switches files
+  useEffect(() => {
+    if (!isTranscoding || isLoading || !videoRef.current) return;
+
+    const player = new TranscodePlayer(videoRef.current, videoPath, (message) => {
+      setHasError(true);
+      setErrorMessage(message);
+    });
+    player.start(0);
+
+    return () => {
+      player.stop();
+    };
+  }, [isTranscoding, isLoading, videoPath]);
+
   // Enforce height constraints continuously using ResizeObserver
   useEffect(() => {
     if (!videoContainerRef.current) return;
@@ -268,10 +208,10 @@ export default function VideoPlayer({ videoPath }: VideoPlayerProps) {
           <div className="flex items-center justify-center h-full">
             <p className="text-xs text-vscode-text-secondary">Loading video...</p>
           </div>
-        ) : videoUrl ? (
+        ) : videoUrl || isTranscoding ? (
           <video
             ref={videoRef}
-            src={videoUrl}
+            src={videoUrl || undefined}
             controls
             className="max-w-full max-h-full object-contain"
             style={{ maxHeight: '330px', maxWidth: '100%', height: 'auto', width: 'auto' }}
@@ -324,7 +264,7 @@ export default function VideoPlayer({ videoPath }: VideoPlayerProps) {
                   errorMsg = "Video loading was aborted. The file path might be incorrect or the file was moved.";
                   break;
                 case error.MEDIA_ERR_NETWORK:
-                  errorMsg = "Network error while loading video. The file might not be accessible through the Tauri asset protocol.";
+                  errorMsg = "Network error while loading video. The file might not be accessible through the media protocol.";
                   break;
                 case error.MEDIA_ERR_DECODE:
                   errorMsg = "Video codec not supported. Even though the file is MP4, it may use an unsupported codec. Please ensure the video uses H.264 codec (not H.265/HEVC). Try re-converting with explicit H.264 settings or use the 'Open in External Player' button.";
@@ -1,11 +1,8 @@
-import { useState } from "react";
+import { useEffect, useRef, useState } from "react";
 import { invoke } from "@tauri-apps/api/core";
+import { getCurrentWebview } from "@tauri-apps/api/webview";
 
-interface VideoInfo {
-  path: string;
-  name: string;
-  size: number;
-}
+import { VideoInfo } from "../hooks/useConversion";
 
 interface VideoSelectorProps {
   onVideoSelected: (video: VideoInfo) => void;
@@ -14,6 +11,9 @@ interface VideoSelectorProps {
 export default function VideoSelector({ onVideoSelected }: VideoSelectorProps) {
   const [isDragging, setIsDragging] = useState(false);
   const [isLoading, setIsLoading] = useState(false);
+  // The drop listener is registered once, so it reads the latest callback from here
+  const onVideoSelectedRef = useRef(onVideoSelected);
+  onVideoSelectedRef.current = onVideoSelected;
 
   const handleSelectVideo = async () => {
     setIsLoading(true);
@@ -28,40 +28,48 @@ export default function VideoSelector({ onVideoSelected }: VideoSelectorProps) {
     }
   };
 
-  const handleDragOver = (e: React.DragEvent) => {
-    e.preventDefault();
-    setIsDragging(true);
-  };
-
-  const handleDragLeave = (e: React.DragEvent) => {
-    e.preventDefault();
-    setIsDragging(false);
-  };
-
-  const handleDrop = async (e: React.DragEvent) => {
-    e.preventDefault();
-    setIsDragging(false);
-    
-    const files = Array.from(e.dataTransfer.files);
-    const videoFile = files.find(file => 
-      file.type.startsWith('video/') || 
-      /\.(mp4|avi|mov|mkv|webm|flv|wmv|m4v)$/i.test(file.name)
-    );
-
-    if (videoFile) {
-      // For drag and drop, we'll use the file path directly
-      // Note: Tauri's file dialog is more reliable, but we can handle dropped files
-      const videoInfo: VideoInfo = {
-        path: (videoFile as any).path || videoFile.name,
-        name: videoFile.name,
-        size: videoFile.size,
-      };
-      onVideoSelected(videoInfo);
-    } else {
+  // Tauri handles file drops on the window itself (HTML drop events never arrive) and
+  // reports the real paths. The backend checks the content, so files with a wrong or
+  // missing extension are accepted as long as they really are videos.
+  const handleDroppedPaths = async (paths: string[]) => {
+    if (paths.length === 0) {
       alert("Please drop a valid video file");
+      return;
+    }
+
+    const errors: string[] = [];
+    for (const filePath of paths) {
+      try {
+        const video = await invoke<VideoInfo>("inspect_video_file", { filePath });
+        if (video.warning) {
+          console.warn("Dropped file format mismatch:", video.warning);
+        }
+        onVideoSelectedRef.current(video);
+        return;
+      } catch (error) {
+        errors.push(String(error));
+      }
     }
+    alert(`Please drop a valid video file\n${errors.join("\n")}`);
   };
 
+  useEffect(() => {
+    const unlisten = getCurrentWebview().onDragDropEvent((event) => {
+      const payload = event.payload;
+      if (payload.type === "enter" || payload.type === "over") {
+        setIsDragging(true);
+      } else if (payload.type === "leave") {
+        setIsDragging(false);
+      } else if (payload.type === "drop") {
+        setIsDragging(false);
+        handleDroppedPaths(payload.paths);
+      }
+    });
+    return () => {
+      unlisten.then((stop) => stop());
+    };
+  }, []);
+
   return (
     <div
       className={`border-2 border-dashed rounded-lg p-4 text-center transition-colors ${
@@ -69,9 +77,6 @@ export default function VideoSelector({ onVideoSelected }: VideoSelectorProps) {
           ? "border-blue-500 bg-blue-900/20"
           : "border-vscode-border bg-vscode-bg hover:border-vscode-text-secondary"
       }`}
-      onDragOver={handleDragOver}
-      onDragLeave={handleDragLeave}
-      onDrop={handleDrop}
     >
       <div className="flex flex-col items-center space-y-2">
         <svg
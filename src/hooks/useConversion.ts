@@ -6,6 +6,48 @@ export interface VideoInfo {
   path: string;
   name: string;
   size: number;
+  format?: string | null; // Detected from the file content
+  warning?: string | null; // Extension and content disagree
+}
+
+export interface LoudnessMeasurement {
+  integrated: number;
+  true_peak: number;
+  lra: number;
+  threshold: number;
+}
+
+export interface LoudnessReport {
+  target: { integrated: number; true_peak: number; lra: number };
+  before: LoudnessMeasurement;
+  after: LoudnessMeasurement | null;
+}
+
+export interface CrfTrial {
+  crf: number;
+  score: number;
+  sample_bytes: number;
+}
+
+export interface CrfSearchResult {
+  crf: number;
+  metric: "vmaf" | "ssim";
+  score: number;
+  met_target: boolean;
+  predicted_size: number;
+  predicted_bitrate: number;
+  trials: CrfTrial[];
+}
+
+export interface ConvertResult {
+  output_path: string;
+  loudness: LoudnessReport | null;
+  crf_search: CrfSearchResult | null;
+}
+
+export interface PreviewResult {
+  output_path: string;
+  file_size: number;
 }
 
 export type ConversionStatus = "idle" | "converting" | "completed" | "error";
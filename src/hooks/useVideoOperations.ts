@@ -1,5 +1,5 @@
 import { invoke } from "@tauri-apps/api/core";
-import { VideoInfo, PreviewType, ConversionStatus } from "./useConversion";
+import { VideoInfo, PreviewType, ConversionStatus, ConvertResult, PreviewResult } from "./useConversion";
 import { getOutputPath, getPreviewPath } from "../utils/pathUtils";
 
 interface UseVideoOperationsParams {
@@ -36,7 +36,7 @@ export function useVideoOperations({
 
     try {
       const outputPathValue = getOutputPath(selectedVideo.path, format, workspacePath);
-      const result = await invoke<string>("convert_video", {
+      const result = await invoke<ConvertResult>("convert_video", {
         options: {
           input_path: selectedVideo.path,
           output_path: outputPathValue,
@@ -44,7 +44,7 @@ export function useVideoOperations({
         },
       });
 
-      setOutputPath(result);
+      setOutputPath(result.output_path);
       setConversionStatus("completed");
       setConversionProgress(100);
       setTimeout(() => {
@@ -76,7 +76,7 @@ export function useVideoOperations({
 
     try {
       const previewPathValue = getPreviewPath(selectedVideo.path, previewType, workspacePath);
-      const result = await invoke<string>("generate_preview", {
+      const result = await invoke<PreviewResult>("generate_preview", {
         options: {
           input_path: selectedVideo.path,
           output_path: previewPathValue,
@@ -85,7 +85,7 @@ export function useVideoOperations({
         },
       });
 
-      setPreviewPath(result);
+      setPreviewPath(result.output_path);
       setConversionStatus("completed");
       setConversionProgress(100);
       setTimeout(() => {
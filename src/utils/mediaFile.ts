@@ -0,0 +1,16 @@
+import { convertFileSrc } from "@tauri-apps/api/core";
+
+// Returned by read_preview_file and read_video_file; the file itself is loaded
+// through the ripley:// media protocol instead of being inlined as a data URL
+export interface MediaFile {
+  path: string;
+  mime_type: string;
+  size: number;
+  modified_ms: number;
+}
+
+// The modification time makes regenerated previews at the same path bypass the cache
+export const mediaFileUrl = (file: MediaFile): string =>
+  `${convertFileSrc(file.path, "ripley")}?v=${file.modified_ms}`;
+
+export const isImageFile = (file: MediaFile): boolean => file.mime_type.startsWith("image/");
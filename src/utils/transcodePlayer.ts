@@ -0,0 +1,227 @@
+import { convertFileSrc, invoke } from "@tauri-apps/api/core";
+
+export interface PlaybackInfo {
+  playlist_path: string;
+  start: number;
+  duration: number | null;
+  mime_type: string;
+}
+
+// Containers the webview cannot demux, whatever codec they hold. The MIME type comes
+// from the file content, so this also catches misnamed files.
+const NATIVE_CONTAINERS = ["video/mp4", "video/quicktime", "video/webm", "video/ogg"];
+// Video codecs the webview decodes natively (names as reported by probe_video)
+const NATIVE_CODECS = ["h264", "vp8", "vp9", "av1", "theora"];
+
+const POLL_INTERVAL_MS = 500;
+const MAX_BUFFER_AHEAD = 60; // Seconds fetched ahead of the playhead before pausing downloads
+const KEEP_BEHIND = 30; // Seconds kept behind the playhead before evicting
+
+export const needsTranscode = (mimeType: string, videoCodec?: string | null): boolean => {
+  // Audio-only files go to the native element; the transcode always produces video
+  if (mimeType.startsWith("audio/")) {
+    return false;
+  }
+  if (!NATIVE_CONTAINERS.includes(mimeType)) {
+    return true;
+  }
+  return !!videoCodec && !NATIVE_CODECS.includes(videoCodec.toLowerCase());
+};
+
+const siblingPath = (filePath: string, name: string): string => {
+  const separator = Math.max(filePath.lastIndexOf("/"), filePath.lastIndexOf("\\"));
+  return filePath.slice(0, separator + 1) + name;
+};
+
+const sleep = (ms: number) => new Promise((resolve) => setTimeout(resolve, ms));
+
+// start/stop calls are queued so a closing player's stop can't kill the next player's session
+let playbackQueue: Promise<unknown> = Promise.resolve();
+const queuePlayback = <T>(call: () => Promise<T>): Promise<T> => {
+  const result = playbackQueue.then(call, call);
+  playbackQueue = result.catch(() => {});
+  return result;
+};
+
+// Plays a source through the start_playback live transcode: fMP4 segments from the
+// HLS playlist are appended to a MediaSource, and seeking outside what has been
+// transcoded restarts the transcode at the new position
+export class TranscodePlayer {
+  private video: HTMLVideoElement;
+  private inputPath: string;
+  private session = 0;
+  private objectUrl: string | null = null;
+  private restarting = false;
+  private onError: (message: string) => void;
+
+  constructor(video: HTMLVideoElement, inputPath: string, onError: (message: string) => void) {
+    this.video = video;
+    this.inputPath = inputPath;
+    this.onError = onError;
+    this.video.addEventListener("seeking", this.handleSeeking);
+  }
+
+  async start(at = 0): Promise<void> {
+    const session = ++this.session;
+    this.restarting = true;
+
+    let info: PlaybackInfo;
+    try {
+      info = await queuePlayback(() =>
+        invoke<PlaybackInfo>("start_playback", {
+          options: { input_path: this.inputPath, start: at },
+        })
+      );
+    } catch (error) {
+      this.restarting = false;
+      if (session === this.session) {
+        this.onError(String(error));
+      }
+      return;
+    }
+    if (session !== this.session) {
+      return;
+    }
+
+    // Replacing the source pauses the element, carry on playing after a seek
+    const resume = !this.video.paused;
+    const mediaSource = new MediaSource();
+    this.revokeObjectUrl();
+    this.objectUrl = URL.createObjectURL(mediaSource);
+    this.video.src = this.objectUrl;
+    await new Promise((resolve) => mediaSource.addEventListener("sourceopen", resolve, { once: true }));
+    if (session !== this.session) {
+      return;
+    }
+
+    if (info.duration) {
+      // Full length, so the native scrubber covers the whole source
+      mediaSource.duration = info.duration;
+    }
+    const sourceBuffer = mediaSource.addSourceBuffer(info.mime_type);
+    // Segments start at zero, shift them to their position in the source
+    sourceBuffer.mode = "sequence";
+    sourceBuffer.timestampOffset = info.start;
+
+    // Only the default start position until data arrives; the element seeks there once the
+    // init segment is in, so seeks stay ignored until the first media segment is appended
+    this.video.currentTime = info.start;
+    if (resume) {
+      this.video.play().catch(() => {});
+    }
+
+    try {
+      await this.feed(session, info, mediaSource, sourceBuffer);
+    } catch (error) {
+      if (session === this.session) {
+        this.restarting = false;
+        this.onError(`Playback failed: ${error}`);
+      }
+    }
+  }
+
+  async stop(): Promise<void> {
+    this.session++;
+    this.video.removeEventListener("seeking", this.handleSeeking);
+    this.video.removeAttribute("src");
+    this.video.load();
+    this.revokeObjectUrl();
+    await queuePlayback(() => invoke("stop_playback")).catch(() => {});
+  }
+
+  private handleSeeking = () => {
+    if (this.restarting) {
+      return;
+    }
+    const target = this.video.currentTime;
+    const buffered = this.video.buffered;
+    for (let i = 0; i < buffered.length; i++) {
+      if (target >= buffered.start(i) && target <= buffered.end(i)) {
+        return;
+      }
+    }
+    this.start(target);
+  };
+
+  private async feed(session: number, info: PlaybackInfo, mediaSource: MediaSource, sourceBuffer: SourceBuffer) {
+    const appended = new Set<string>();
+    const append = async (path: string) => {
+      const response = await fetch(convertFileSrc(path, "ripley"));
+      if (!response.ok) {
+        throw new Error(`segment request failed (${response.status})`);
+      }
+      const data = await response.arrayBuffer();
+      if (session !== this.session) {
+        return;
+      }
+      sourceBuffer.appendBuffer(data);
+      await new Promise((resolve) => sourceBuffer.addEventListener("updateend", resolve, { once: true }));
+    };
+
+    await append(siblingPath(info.playlist_path, "init.mp4"));
+
+    while (session === this.session) {
+      const response = await fetch(convertFileSrc(info.playlist_path, "ripley"), { cache: "no-store" });
+      const playlist = response.ok ? await response.text() : "";
+      const segments = playlist
+        .split("\n")
+        .map((line) => line.trim())
+        .filter((line) => line && !line.startsWith("#"));
+
+      for (const segment of segments) {
+        if (appended.has(segment)) {
+          continue;
+        }
+        while (session === this.session && this.bufferedAhead() > MAX_BUFFER_AHEAD) {
+          await sleep(POLL_INTERVAL_MS);
+        }
+        if (session !== this.session) {
+          return;
+        }
+        await this.evictPlayed(sourceBuffer);
+        await append(siblingPath(info.playlist_path, segment));
+        appended.add(segment);
+        if (session === this.session) {
+          this.restarting = false;
+        }
+      }
+
+      if (playlist.includes("#EXT-X-ENDLIST")) {
+        if (session === this.session) {
+          this.restarting = false;
+        }
+        if (session === this.session && mediaSource.readyState === "open") {
+          mediaSource.endOfStream();
+        }
+        return;
+      }
+      await sleep(POLL_INTERVAL_MS);
+    }
+  }
+
+  private bufferedAhead(): number {
+    const buffered = this.video.buffered;
+    if (buffered.length === 0) {
+      return 0;
+    }
+    return buffered.end(buffered.length - 1) - this.video.currentTime;
+  }
+
+  // Drop what is well behind the playhead so long files do not exhaust the buffer quota
+  private async evictPlayed(sourceBuffer: SourceBuffer) {
+    const buffered = sourceBuffer.buffered;
+    const cutoff = this.video.currentTime - KEEP_BEHIND;
+    if (buffered.length === 0 || buffered.start(0) >= cutoff) {
+      return;
+    }
+    sourceBuffer.remove(buffered.start(0), cutoff);
+    await new Promise((resolve) => sourceBuffer.addEventListener("updateend", resolve, { once: true }));
+  }
+
+  private revokeObjectUrl() {
+    if (this.objectUrl) {
+      URL.revokeObjectURL(this.objectUrl);
+      this.objectUrl = null;
+    }
+  }
+}
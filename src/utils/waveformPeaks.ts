@@ -0,0 +1,59 @@
+import { invoke } from "@tauri-apps/api/core";
+
+export interface WaveformPeaks {
+  sampleRate: number;
+  samplesPerBucket: number;
+  secondsPerBucket: number;
+  min: Float32Array;
+  max: Float32Array;
+  rms: Float32Array;
+}
+
+// Binary layout written by get_waveform_peaks (little-endian):
+// "RFPK", u32 version, u32 sample rate, u32 samples per bucket, u32 bucket count,
+// then f32 min, max, rms for each bucket
+export const parseWaveformPeaks = (buffer: ArrayBuffer): WaveformPeaks => {
+  const view = new DataView(buffer);
+  const magic = String.fromCharCode(
+    view.getUint8(0),
+    view.getUint8(1),
+    view.getUint8(2),
+    view.getUint8(3)
+  );
+  if (magic !== "RFPK" || view.getUint32(4, true) !== 1) {
+    throw new Error("Unsupported waveform peak data");
+  }
+
+  const sampleRate = view.getUint32(8, true);
+  const samplesPerBucket = view.getUint32(12, true);
+  const bucketCount = view.getUint32(16, true);
+
+  const min = new Float32Array(bucketCount);
+  const max = new Float32Array(bucketCount);
+  const rms = new Float32Array(bucketCount);
+  for (let i = 0; i < bucketCount; i++) {
+    const offset = 20 + i * 12;
+    min[i] = view.getFloat32(offset, true);
+    max[i] = view.getFloat32(offset + 4, true);
+    rms[i] = view.getFloat32(offset + 8, true);
+  }
+
+  return {
+    sampleRate,
+    samplesPerBucket,
+    secondsPerBucket: samplesPerBucket / sampleRate,
+    min,
+    max,
+    rms,
+  };
+};
+
+export const getWaveformPeaks = async (inputPath: string, buckets?: number): Promise<WaveformPeaks> => {
+  const buffer = await invoke<ArrayBuffer>("get_waveform_peaks", {
+    options: {
+      input_path: inputPath,
+      buckets,
+    },
+  });
+  return parseWaveformPeaks(buffer);
+};